@@ -0,0 +1,42 @@
+//! Command line tool for running [Restate](https://restate.dev/) services on
+//! [Cloudflare Workers](https://developers.cloudflare.com/workers/).
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+mod register;
+
+#[derive(Parser)]
+#[command(name = "restate-worker", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Registers a worker deployment with the Restate admin API.
+    Register(register::Args),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Register(args) => register::run(&args).map(|deployment| {
+            println!("Registered deployment {}", deployment.id());
+            for service in deployment.services() {
+                println!("  {} (revision {})", service.name(), service.revision());
+            }
+        }),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::time::Duration;
+
+use restate_worker::admin::{DEPLOYMENTS_PATH, RegisterDeployment, RegisteredDeployment};
+use serde::Deserialize;
+use ureq::Agent;
+use ureq::http::StatusCode;
+
+/// Media type of the discovery manifest requested from the endpoint.
+const DISCOVERY_CONTENT_TYPE: &str = "application/vnd.restate.endpointmanifest.v2+json";
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Public URL of the worker serving the Restate endpoint, including any
+    /// path prefix (e.g. https://greeter.example.workers.dev/restate).
+    worker_url: String,
+
+    /// URL of the Restate admin API.
+    #[arg(
+        long,
+        env = "RESTATE_ADMIN_URL",
+        default_value = "http://localhost:9070"
+    )]
+    admin_url: String,
+
+    /// Bearer token authenticating with the admin API (e.g. a Restate Cloud
+    /// API key).
+    #[arg(long, env = "RESTATE_AUTH_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    /// Worker version the deployment is pinned to, appended to the worker URL
+    /// as `/v/{version_id}`.
+    #[arg(long)]
+    version_id: Option<String>,
+
+    /// Overrides an existing deployment registered at the same URL.
+    #[arg(long)]
+    force: bool,
+
+    /// Registers the deployment over HTTP/2 instead of HTTP/1.1.
+    #[arg(long)]
+    http2: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("request to {0} failed: {1}")]
+    Http(String, #[source] ureq::Error),
+
+    #[error("discovery at {url} failed with status {status}: {body}")]
+    Discovery {
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+
+    #[error("endpoint at {0} does not expose any service")]
+    NoServices(String),
+
+    #[error("a deployment is already registered at {0} (use --force to override it): {1}")]
+    Conflict(String, String),
+
+    #[error("admin API responded with status {status}: {body}")]
+    Admin { status: StatusCode, body: String },
+}
+
+/// Discovery manifest produced by the Restate endpoint.
+#[derive(Deserialize)]
+struct Manifest {
+    services: Vec<ManifestService>,
+}
+
+#[derive(Deserialize)]
+struct ManifestService {
+    name: String,
+}
+
+/// Outcome of the discovery of the endpoint by the CLI.
+enum Discovery {
+    /// Names of the services exposed by the endpoint.
+    Services(Vec<String>),
+    /// The endpoint rejected the request, as it requires authentication
+    /// (e.g. Restate request identity, Cloudflare Access or a bearer token) or
+    /// only accepts clients from some IP ranges.
+    Denied { status: StatusCode, body: String },
+}
+
+/// Checks that the worker serves a Restate endpoint, then registers it with
+/// the admin API.
+pub fn run(args: &Args) -> Result<RegisteredDeployment, Error> {
+    let agent = agent();
+    let url = deployment_url(args);
+
+    match discover(&agent, &url)? {
+        Discovery::Services(services) if services.is_empty() => {
+            return Err(Error::NoServices(url));
+        }
+        Discovery::Services(services) => {
+            eprintln!("Discovered services: {}", services.join(", "))
+        }
+        // Restate discovers the endpoint itself when registering it.
+        Discovery::Denied { status, body } => {
+            eprintln!("Skipping discovery: discovery requires authentication ({status}: {body})")
+        }
+    }
+
+    let mut request = RegisterDeployment::new(url).force(args.force);
+    if args.http2 {
+        request = request.http2();
+    }
+
+    register(&agent, args, &request)
+}
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(Duration::from_secs(30)))
+        .build()
+        .into()
+}
+
+fn deployment_url(args: &Args) -> String {
+    let url = args.worker_url.trim_end_matches('/');
+
+    match &args.version_id {
+        Some(version_id) => format!("{url}{}", restate_worker::versioned_path(version_id)),
+        None => url.to_owned(),
+    }
+}
+
+/// Fetches the names of the services exposed by the endpoint.
+fn discover(agent: &Agent, url: &str) -> Result<Discovery, Error> {
+    let discover_url = format!("{url}/discover");
+    let mut response = agent
+        .get(&discover_url)
+        .header("accept", DISCOVERY_CONTENT_TYPE)
+        .call()
+        .map_err(|e| Error::Http(discover_url.clone(), e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.body_mut().read_to_string().unwrap_or_default();
+
+        return match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Ok(Discovery::Denied { status, body })
+            }
+            status => Err(Error::Discovery {
+                url: discover_url,
+                status,
+                body,
+            }),
+        };
+    }
+
+    let manifest: Manifest = response
+        .body_mut()
+        .read_json()
+        .map_err(|e| Error::Http(discover_url, e))?;
+
+    Ok(Discovery::Services(
+        manifest
+            .services
+            .into_iter()
+            .map(|service| service.name)
+            .collect(),
+    ))
+}
+
+fn register(
+    agent: &Agent,
+    args: &Args,
+    request: &RegisterDeployment,
+) -> Result<RegisteredDeployment, Error> {
+    let url = format!("{}{DEPLOYMENTS_PATH}", args.admin_url.trim_end_matches('/'));
+
+    let mut builder = agent.post(&url);
+    if let Some(token) = &args.auth_token {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+
+    let mut response = builder
+        .send_json(request)
+        .map_err(|e| Error::Http(url.clone(), e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.body_mut().read_to_string().unwrap_or_default();
+
+        return Err(match status {
+            StatusCode::CONFLICT => Error::Conflict(request.uri().to_owned(), body),
+            status => Error::Admin { status, body },
+        });
+    }
+
+    response
+        .body_mut()
+        .read_json()
+        .map_err(|e| Error::Http(url, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::{self, JoinHandle};
+    use tiny_http::{Response, Server};
+
+    /// Request received by the stand-in server.
+    struct Received {
+        url: String,
+        authorization: Option<String>,
+        body: String,
+    }
+
+    /// Starts a server standing in for both the worker and the Restate admin
+    /// API, responding to each path with the given status and body.
+    fn stand_in(
+        routes: &[(&'static str, u16, &'static str)],
+    ) -> (String, JoinHandle<Vec<Received>>) {
+        let routes = routes.to_vec();
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+
+            for _ in &routes {
+                let mut request = server.recv().unwrap();
+                let (_, status, body) = routes
+                    .iter()
+                    .find(|(path, _, _)| request.url().ends_with(path))
+                    .unwrap();
+
+                let mut content = String::new();
+                request.as_reader().read_to_string(&mut content).unwrap();
+                received.push(Received {
+                    url: request.url().to_owned(),
+                    authorization: request
+                        .headers()
+                        .iter()
+                        .find(|header| header.field.equiv("authorization"))
+                        .map(|header| header.value.to_string()),
+                    body: content,
+                });
+
+                let response = Response::from_string(*body).with_status_code(*status);
+                request.respond(response).unwrap();
+            }
+
+            received
+        });
+
+        (url, handle)
+    }
+
+    fn args(url: &str) -> Args {
+        Args {
+            worker_url: format!("{url}/restate/"),
+            admin_url: url.to_owned(),
+            auth_token: Some("secret".to_owned()),
+            version_id: Some("abc".to_owned()),
+            force: true,
+            http2: false,
+        }
+    }
+
+    #[test]
+    fn registers_deployment() {
+        let (url, server) = stand_in(&[
+            (
+                "/discover",
+                200,
+                r#"{"protocolMode": "REQUEST_RESPONSE", "services": [{"name": "Greeter"}]}"#,
+            ),
+            (
+                "/deployments",
+                201,
+                r#"{"id": "dp_1", "services": [{"name": "Greeter", "revision": 1}]}"#,
+            ),
+        ]);
+
+        let deployment = run(&args(&url)).unwrap();
+        assert_eq!(deployment.id(), "dp_1");
+
+        let received = server.join().unwrap();
+        assert_eq!(received[0].url, "/restate/v/abc/discover");
+
+        let registration: serde_json::Value = serde_json::from_str(&received[1].body).unwrap();
+        assert_eq!(received[1].authorization.as_deref(), Some("Bearer secret"));
+        assert_eq!(
+            registration,
+            serde_json::json!({
+                "uri": format!("{url}/restate/v/abc"),
+                "force": true,
+                "use_http_11": true,
+            })
+        );
+    }
+
+    #[test]
+    fn registers_endpoint_denying_discovery() {
+        for (status, body) in [
+            (401, "unauthorized: missing bearer token"),
+            (403, "forbidden: client IP 203.0.113.1 is not allowed"),
+        ] {
+            let (url, server) = stand_in(&[
+                ("/discover", status, body),
+                ("/deployments", 201, r#"{"id": "dp_1", "services": []}"#),
+            ]);
+
+            run(&args(&url)).unwrap();
+            assert_eq!(server.join().unwrap().len(), 2, "{status}");
+        }
+    }
+
+    #[test]
+    fn reports_discovery_failures() {
+        let (url, server) = stand_in(&[("/discover", 404, "not found")]);
+
+        let error = run(&args(&url)).unwrap_err();
+        server.join().unwrap();
+
+        assert!(matches!(error, Error::Discovery { .. }), "{error}");
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let (url, server) = stand_in(&[
+            ("/discover", 200, r#"{"services": [{"name": "Greeter"}]}"#),
+            ("/deployments", 409, "deployment already exists"),
+        ]);
+
+        let error = run(&args(&url)).unwrap_err();
+        server.join().unwrap();
+
+        assert!(matches!(error, Error::Conflict(..)), "{error}");
+    }
+
+    #[test]
+    fn rejects_endpoint_without_services() {
+        let (url, server) = stand_in(&[("/discover", 200, r#"{"services": []}"#)]);
+
+        let error = run(&args(&url)).unwrap_err();
+        server.join().unwrap();
+
+        assert!(matches!(error, Error::NoServices(_)), "{error}");
+    }
+}
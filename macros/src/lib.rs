@@ -0,0 +1,113 @@
+//! Procedural macros for [restate-worker](https://docs.rs/restate-worker).
+//!
+//! This crate is not meant to be used directly: the macros are re-exported by
+//! `restate-worker`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Error, ItemFn, parse_macro_input};
+
+/// Generates the `fetch` entrypoint of a worker serving a Restate endpoint.
+///
+/// See `restate_worker::main` for details.
+#[proc_macro_attribute]
+pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return Error::new(
+            TokenStream2::from(attr).span(),
+            "#[restate_worker::main] does not take any arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let input = parse_macro_input!(item as ItemFn);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: ItemFn) -> syn::Result<TokenStream2> {
+    let sig = &input.sig;
+
+    if sig.asyncness.is_some() {
+        return Err(Error::new(
+            sig.asyncness.span(),
+            "#[restate_worker::main] function must not be async",
+        ));
+    }
+
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "#[restate_worker::main] function must not be generic",
+        ));
+    }
+
+    let ident = &sig.ident;
+    let call = match sig.inputs.len() {
+        0 => quote! { #ident() },
+        1 => quote! { #ident(env) },
+        _ => {
+            return Err(Error::new(
+                sig.inputs.span(),
+                "#[restate_worker::main] function must take either no arguments or `&worker::Env`",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        #input
+
+        #[::worker::event(fetch)]
+        async fn __restate_worker_fetch(
+            req: ::worker::HttpRequest,
+            env: ::worker::Env,
+            ctx: ::worker::Context,
+        ) -> ::worker::Result<::worker::HttpResponse> {
+            static HANDLER: ::restate_worker::LazyHandler = ::restate_worker::LazyHandler::new(
+                |env| ::restate_worker::IntoHandler::into_handler(#call, env),
+            );
+
+            ::restate_worker::__private::fetch(&HANDLER, req, env, ctx).await
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_env_when_requested() {
+        let without_env = expand(syn::parse_quote! {
+            fn endpoint() -> Endpoint { Endpoint::builder().build() }
+        })
+        .unwrap()
+        .to_string();
+        let with_env = expand(syn::parse_quote! {
+            fn endpoint(env: &Env) -> Endpoint { Endpoint::builder().build() }
+        })
+        .unwrap()
+        .to_string();
+
+        assert!(without_env.contains("into_handler (endpoint () , env)"));
+        assert!(with_env.contains("into_handler (endpoint (env) , env)"));
+    }
+
+    #[test]
+    fn rejects_unsupported_signatures() {
+        let inputs: [ItemFn; 3] = [
+            syn::parse_quote! { async fn endpoint() -> Endpoint { todo!() } },
+            syn::parse_quote! { fn endpoint<T>() -> Endpoint { todo!() } },
+            syn::parse_quote! { fn endpoint(env: &Env, ctx: &Context) -> Endpoint { todo!() } },
+        ];
+
+        for input in inputs {
+            assert!(expand(input).is_err());
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, Method};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use worker::Env;
+use worker::send::SendFuture;
+
+use crate::Error;
+use crate::bindings;
+use crate::fetch;
+
+/// Name of the variable holding the Cloudflare Access team domain (e.g.
+/// `myteam.cloudflareaccess.com`).
+pub const ACCESS_TEAM_DOMAIN_BINDING: &str = "CF_ACCESS_TEAM_DOMAIN";
+
+/// Name of the variable holding the application audience (AUD) tag of the
+/// Cloudflare Access application protecting the worker.
+pub const ACCESS_AUDIENCE_BINDING: &str = "CF_ACCESS_AUD";
+
+/// Header holding the JWT issued by Cloudflare Access for the request.
+const ACCESS_JWT_HEADER: HeaderName = HeaderName::from_static("cf-access-jwt-assertion");
+
+/// Path of the signing keys of a Cloudflare Access team.
+const CERTS_PATH: &str = "/cdn-cgi/access/certs";
+
+/// Minimum delay between two fetches of the signing keys.
+const KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Validation of the [Cloudflare Access](https://developers.cloudflare.com/cloudflare-one/identity/authorization-cookie/validating-json/)
+/// JWT sent with each request.
+///
+/// Protects the endpoint of a self-hosted Restate cluster reaching the
+/// worker through Cloudflare Access (e.g. with a service token). Requests
+/// without a token signed by the team, issued for the audience of the Access
+/// application, are rejected with `401 Unauthorized`.
+///
+/// The signing keys of the team are fetched on first use and cached in
+/// memory, then fetched again when a token is signed with an unknown key, at
+/// most once every 30 seconds.
+///
+/// ```rust,ignore
+/// use restate_worker::{CloudflareAccess, Handler};
+///
+/// let handler = Handler::builder(endpoint)
+///     .cloudflare_access(CloudflareAccess::from_env(&env)?)
+///     .build();
+/// ```
+pub struct CloudflareAccess {
+    issuer: String,
+    audience: String,
+    keys: Keys,
+}
+
+/// Signing keys of the Cloudflare Access team.
+enum Keys {
+    Static(JwkSet),
+    Fetched(Mutex<FetchedKeys>),
+}
+
+/// Signing keys fetched from the team, with the time of the last fetch (in
+/// milliseconds since the Unix epoch).
+#[derive(Default)]
+struct FetchedKeys {
+    jwks: Option<JwkSet>,
+    fetched_at: Option<u64>,
+}
+
+impl CloudflareAccess {
+    /// Validates tokens issued by the given team (e.g. `myteam` or
+    /// `myteam.cloudflareaccess.com`) for the given application audience.
+    pub fn new(team_domain: &str, audience: impl Into<String>) -> Self {
+        let domain = team_domain
+            .trim_start_matches("https://")
+            .trim_end_matches('/');
+        let issuer = if domain.contains('.') {
+            format!("https://{domain}")
+        } else {
+            format!("https://{domain}.cloudflareaccess.com")
+        };
+
+        Self {
+            issuer,
+            audience: audience.into(),
+            keys: Keys::Fetched(Mutex::default()),
+        }
+    }
+
+    /// Validates tokens issued by the team in [`ACCESS_TEAM_DOMAIN_BINDING`]
+    /// for the audience in [`ACCESS_AUDIENCE_BINDING`].
+    pub fn from_env(env: &Env) -> worker::Result<Self> {
+        Ok(Self::new(
+            &bindings::var(env, ACCESS_TEAM_DOMAIN_BINDING)?,
+            bindings::var(env, ACCESS_AUDIENCE_BINDING)?,
+        ))
+    }
+
+    /// Validates tokens with the given keys instead of fetching the keys of
+    /// the team.
+    pub fn jwks(mut self, jwks: JwkSet) -> Self {
+        self.keys = Keys::Static(jwks);
+        self
+    }
+
+    /// Validates the Access token of a request.
+    pub(crate) async fn validate(&self, headers: &HeaderMap) -> Result<(), Error> {
+        let token = headers
+            .get(ACCESS_JWT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| Error::Unauthorized("missing Cloudflare Access token".to_owned()))?;
+
+        let invalid = |e: jsonwebtoken::errors::Error| {
+            Error::Unauthorized(format!("invalid Cloudflare Access token: {e}"))
+        };
+
+        let header = jsonwebtoken::decode_header(token).map_err(invalid)?;
+        let kid = header.kid.ok_or_else(|| {
+            Error::Unauthorized("Cloudflare Access token does not identify its key".to_owned())
+        })?;
+        let key = DecodingKey::from_jwk(&self.key(&kid).await?).map_err(invalid)?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[&self.audience]);
+        validation.set_issuer(&[&self.issuer]);
+
+        jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation).map_err(invalid)?;
+        Ok(())
+    }
+
+    /// Returns the signing key with the given id.
+    async fn key(&self, kid: &str) -> Result<Jwk, Error> {
+        match &self.keys {
+            Keys::Static(jwks) => jwks.find(kid).cloned().ok_or_else(|| unknown_key(kid)),
+            Keys::Fetched(keys) => {
+                let now = worker::Date::now().as_millis();
+                fetched_key(keys, kid, now, || self.fetch_keys()).await
+            }
+        }
+    }
+
+    async fn fetch_keys(&self) -> Result<JwkSet, Error> {
+        let url = format!("{}{CERTS_PATH}", self.issuer);
+        let unavailable =
+            |e: String| Error::Unavailable(format!("cannot fetch Cloudflare Access keys: {e}"));
+
+        // JavaScript futures are not Send, but Workers isolates are single-threaded.
+        let response = SendFuture::new(fetch::fetch(
+            Method::GET,
+            &url,
+            &HeaderMap::new(),
+            &Bytes::new(),
+        ))
+        .await
+        .map_err(|e| unavailable(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(unavailable(format!("status {}", response.status())));
+        }
+
+        response.json().map_err(|e| unavailable(e.to_string()))
+    }
+}
+
+/// Returns the signing key with the given id, fetching the keys with `fetch`
+/// when it is unknown (e.g. because the keys have been rotated).
+///
+/// The keys are fetched at most once per [`KEYS_REFRESH_INTERVAL`], so that
+/// clients cannot trigger a fetch for every request by sending tokens with
+/// random key ids.
+async fn fetched_key<F>(
+    keys: &Mutex<FetchedKeys>,
+    kid: &str,
+    now: u64,
+    fetch: impl FnOnce() -> F,
+) -> Result<Jwk, Error>
+where
+    F: Future<Output = Result<JwkSet, Error>>,
+{
+    {
+        let mut keys = keys.lock().expect("key cache must not be poisoned");
+        if let Some(key) = keys.jwks.as_ref().and_then(|jwks| jwks.find(kid)) {
+            return Ok(key.clone());
+        }
+
+        let interval = KEYS_REFRESH_INTERVAL.as_millis() as u64;
+        if keys
+            .fetched_at
+            .is_some_and(|fetched_at| now.saturating_sub(fetched_at) < interval)
+        {
+            return Err(match keys.jwks {
+                Some(_) => unknown_key(kid),
+                None => Error::Unavailable("Cloudflare Access keys are not available".to_owned()),
+            });
+        }
+
+        // Recorded before fetching, so that concurrent requests do not fetch the keys as well.
+        keys.fetched_at = Some(now);
+    }
+
+    let jwks = fetch().await?;
+    let key = jwks.find(kid).cloned();
+    keys.lock().expect("key cache must not be poisoned").jwks = Some(jwks);
+
+    key.ok_or_else(|| unknown_key(kid))
+}
+
+fn unknown_key(kid: &str) -> Error {
+    Error::Unauthorized(format!("unknown Cloudflare Access key '{kid}'"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    /// RSA private key (PKCS #1, DER) signing test tokens.
+    const PRIVATE_KEY: &str = concat!(
+        "MIIEowIBAAKCAQEAtaxW9GogK9lhSqzOspjfw92XBw29gq9sGLVMrlfCMzEknyxTz4SfdIn9n1Zb",
+        "Z7QblwJaV2kjT/gaULbuAdUvK49uQtjZ5PkaRFtdcLZDOAFfVmV6zQEevQk5OUCnuuptajuf88V6",
+        "IfDxsKBzDhwP1UJLyPzSTSLbB6vlJeWMTl+FTQWRFASodU+PrRGvWUhuTYnxE5PlHQc40iiKo/Ck",
+        "gYF2fBBrLQj++wxYSlGV48XY/2fnSxY+C5HThkSOQyYa1pb09xrsEOSbEoiMUNWDAl5657Via5D2",
+        "WKkwKIyv2HUC1h0rKIG4uQgDsksvJT4MEWTV/0D4XUI4XqYyXLR9cwIDAQABAoIBADsOvauxriii",
+        "/2Ivn/fmA2PgLn7wBAmuLhUwJq/MPoi2soF9/vbIVa+kRgrcn1UscwWZkbW1q3GNJTzU4rU80Js4",
+        "Aq9/YAHzyoqrKsp/rYVQyOXEknF/Kjdofq4yavQzzgLJRDuSGBxdf6wb1JsjGzCH3ywEp/bEXHB3",
+        "fEPgBcbZ8F+QVUWP1KdM9SGKLrDYfRVl+yu5luvyn6kSStA8nbowviY3B4SHE3Net5NIVcYmL9c1",
+        "y/zoLtt1SZifHdehR9mW4kJdqnWBWz3gxO73ES/uMI3h/LNBc2gPI7xFnyAjYkDzt4KNFU1qPQ7S",
+        "s0yhrUW202G8/7N85uu9c9G0ZpkCgYEA8PPXxOq+ROJH5yzVo65z4aGKNuxPNrcTAk6F+ZZwi6qX",
+        "ka+jWVabuwHT483PDK7cTefnIHWhLqhJ6cKe1cl/3WCe/hG3Q6ZyYeW2K1hLuyHK5Hbdlt947aiN",
+        "jUEQ4MyH5SvIyHxbMPycg9s1KIlmIZ6NF9jLvG5HQEWgUqCEJzkCgYEAwQTJTsLRN0yF9jQ3TvOw",
+        "R4H7vjHC53Hlxi2TRXVNs4uABURVfz0g5YU/8RnNtwNGDnXpuWKcKGRUobW/fWgJwLjGroBPQBnL",
+        "nwcml0NveClatwiynB9KgA0WzWH5D7Da43d3Gc8LZ8LFH3H106r9fFniQwfzPLVu7tZoC8K/PgsC",
+        "gYEA49tEBUSTr6JoqpV8ZnA3x3wyryOi+TQBNuI40cDRJ1KoSK3WhEphtGPTE47xqKXHUajmqYxz",
+        "YyLj2rof6D3Hu/p9//eS3deOPUO0lKLH4uve6VP60oz714rYaWbJZjwkmrRgCC+JMPcBr7NhPrdI",
+        "LKy58n4ilEuY94+gP+LpdgkCgYBafCbvIE86EEqgr7vZ7E2QSDQ+5k8Ldw6TiBwJLMOfTt9WGMHH",
+        "410/m+bs3P7eM8+sycQm3z57hQVTxcMeRB6GVVj2xznfv/f/9jc2JCvmdeSL96zbmaOwQfKVl79N",
+        "hsgmaIPR+ojLPLhyVFc8wmUQ3YY/jEOQCzIVDzg5gzNqHwKBgBfcRgZWBX3iAGl9kl4mbMn+gMl6",
+        "sYj4PmxaPpgooYqiW0N3jQWVzZkhhIe1SKJGEz9rFe4aF+SmJffWkIhB5z1aBj/8f2yi9D7nEhVx",
+        "IDcEjg7YE7vjzKbRs1Sx5MgfTA199UalwLJGklUb7HD+CrgOcy2obQYWl/NpFmAESH8y",
+    );
+
+    /// Modulus of the public key matching [`PRIVATE_KEY`].
+    const MODULUS: &str = concat!(
+        "taxW9GogK9lhSqzOspjfw92XBw29gq9sGLVMrlfCMzEknyxTz4SfdIn9n1ZbZ7QblwJaV2kjT_ga",
+        "ULbuAdUvK49uQtjZ5PkaRFtdcLZDOAFfVmV6zQEevQk5OUCnuuptajuf88V6IfDxsKBzDhwP1UJL",
+        "yPzSTSLbB6vlJeWMTl-FTQWRFASodU-PrRGvWUhuTYnxE5PlHQc40iiKo_CkgYF2fBBrLQj--wxY",
+        "SlGV48XY_2fnSxY-C5HThkSOQyYa1pb09xrsEOSbEoiMUNWDAl5657Via5D2WKkwKIyv2HUC1h0r",
+        "KIG4uQgDsksvJT4MEWTV_0D4XUI4XqYyXLR9cw",
+    );
+
+    pub(crate) const AUDIENCE: &str = "aud-1";
+
+    fn jwks() -> JwkSet {
+        serde_json::from_value(json!({
+            "keys": [{"kty": "RSA", "alg": "RS256", "use": "sig", "kid": "key-1", "n": MODULUS, "e": "AQAB"}],
+        }))
+        .unwrap()
+    }
+
+    pub(crate) fn access() -> CloudflareAccess {
+        CloudflareAccess::new("myteam", AUDIENCE).jwks(jwks())
+    }
+
+    pub(crate) fn token(kid: &str, audience: &str) -> String {
+        use base64::Engine;
+
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(PRIVATE_KEY)
+            .unwrap();
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_owned());
+
+        let claims = json!({
+            "aud": [audience],
+            "iss": "https://myteam.cloudflareaccess.com",
+            "exp": jsonwebtoken::get_current_timestamp() + 60,
+            "sub": "",
+        });
+
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_rsa_der(&der)).unwrap()
+    }
+
+    fn validate(token: Option<String>) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            headers.insert(ACCESS_JWT_HEADER, token.parse().unwrap());
+        }
+
+        block_on(access().validate(&headers))
+    }
+
+    #[test]
+    fn accepts_valid_tokens() {
+        validate(Some(token("key-1", AUDIENCE))).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_tokens() {
+        for token in [
+            None,
+            Some("not-a-jwt".to_owned()),
+            Some(token("key-1", "other-aud")),
+            Some(token("key-2", AUDIENCE)),
+        ] {
+            let error = validate(token).unwrap_err();
+
+            assert!(matches!(error, Error::Unauthorized(_)), "{error}");
+        }
+    }
+
+    #[test]
+    fn rate_limits_key_fetches() {
+        use std::cell::Cell;
+
+        let keys = Mutex::default();
+        let fetches = Cell::new(0);
+        let key = |kid, now| {
+            block_on(fetched_key(&keys, kid, now, || async {
+                fetches.set(fetches.get() + 1);
+                Ok(jwks())
+            }))
+        };
+
+        key("key-1", 0).unwrap();
+        assert_eq!(fetches.get(), 1);
+
+        // Unknown keys do not trigger a fetch until the refresh interval elapses.
+        for now in [1_000, 2_000, 29_999] {
+            let error = key("random", now).unwrap_err();
+            assert!(matches!(error, Error::Unauthorized(_)), "{error}");
+        }
+        key("key-1", 10_000).unwrap();
+        assert_eq!(fetches.get(), 1);
+
+        assert!(key("random", 30_000).is_err());
+        assert!(key("random", 30_001).is_err());
+        assert_eq!(fetches.get(), 2);
+    }
+
+    #[test]
+    fn rate_limits_failed_key_fetches() {
+        use std::cell::Cell;
+
+        let keys = Mutex::default();
+        let fetches = Cell::new(0);
+        let key = |now| {
+            block_on(fetched_key(&keys, "key-1", now, || async {
+                fetches.set(fetches.get() + 1);
+                Err(Error::Unavailable("down".to_owned()))
+            }))
+        };
+
+        for now in [0, 1_000, 30_000] {
+            let error = key(now).unwrap_err();
+            assert_eq!(error.status_code(), http::StatusCode::SERVICE_UNAVAILABLE);
+        }
+        assert_eq!(fetches.get(), 2);
+    }
+}
@@ -0,0 +1,125 @@
+//! Types of the [Restate admin API](https://docs.restate.dev/references/admin-api)
+//! used to register worker deployments.
+
+use serde::{Deserialize, Serialize};
+
+/// Path of the admin API endpoint registering deployments.
+pub const DEPLOYMENTS_PATH: &str = "/deployments";
+
+/// Request registering a deployment with the Restate admin API.
+///
+/// Deployments are registered over HTTP/1.1 by default, which Cloudflare
+/// Workers support in request-response mode.
+///
+/// ```rust,ignore
+/// use restate_worker::admin::RegisterDeployment;
+///
+/// let request = RegisterDeployment::new("https://greeter.example.workers.dev/restate").force(true);
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterDeployment {
+    uri: String,
+    force: bool,
+    use_http_11: bool,
+}
+
+impl RegisterDeployment {
+    /// Creates a request registering the deployment served at `uri`.
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            force: false,
+            use_http_11: true,
+        }
+    }
+
+    /// Overrides an existing deployment registered at the same URI.
+    ///
+    /// Restate rejects the registration with `409 Conflict` otherwise. Only
+    /// force-update a deployment if the services it exposes are compatible
+    /// with the in-flight invocations.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Registers the deployment over HTTP/2 instead of HTTP/1.1.
+    pub fn http2(mut self) -> Self {
+        self.use_http_11 = false;
+        self
+    }
+
+    /// Returns the URI of the deployment.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+}
+
+/// Deployment registered with the Restate admin API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegisteredDeployment {
+    id: String,
+    #[serde(default)]
+    services: Vec<RegisteredService>,
+}
+
+impl RegisteredDeployment {
+    /// Returns the identifier assigned to the deployment by Restate.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the services exposed by the deployment.
+    pub fn services(&self) -> &[RegisteredService] {
+        &self.services
+    }
+}
+
+/// Service exposed by a [`RegisteredDeployment`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegisteredService {
+    name: String,
+    #[serde(default)]
+    revision: u32,
+}
+
+impl RegisteredService {
+    /// Returns the name of the service.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the revision of the service, incremented by Restate every time
+    /// a deployment exposing it is registered.
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registration_format() {
+        let request = RegisterDeployment::new("https://example.com/restate").force(true);
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "uri": "https://example.com/restate",
+                "force": true,
+                "use_http_11": true,
+            })
+        );
+
+        let deployment: RegisteredDeployment = serde_json::from_str(
+            r#"{"id": "dp_1", "services": [{"name": "Greeter", "revision": 2, "ty": "SERVICE"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(deployment.id(), "dp_1");
+        assert_eq!(deployment.services()[0].name(), "Greeter");
+        assert_eq!(deployment.services()[0].revision(), 2);
+    }
+}
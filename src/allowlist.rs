@@ -0,0 +1,198 @@
+use std::net::IpAddr;
+
+use http::{HeaderMap, HeaderName};
+use worker::{Env, Error as WorkerError, Result};
+
+use crate::Error;
+use crate::bindings;
+
+/// Name of the variable holding the IP ranges allowed by
+/// [`IpAllowlist::from_env`].
+///
+/// The value is a comma-separated list of CIDR ranges (e.g. the egress IP
+/// ranges of a Restate Cloud region).
+pub const ALLOWED_IPS_BINDING: &str = "RESTATE_ALLOWED_IPS";
+
+/// Header holding the IP address of the client, set by Cloudflare.
+const CF_CONNECTING_IP: HeaderName = HeaderName::from_static("cf-connecting-ip");
+
+/// Allowlist of the client IP ranges allowed to reach the endpoint.
+///
+/// Reads the client IP from the `CF-Connecting-IP` header set by Cloudflare,
+/// and rejects requests from other IPs with `403 Forbidden`. Meant as defense
+/// in depth, next to request identity verification.
+///
+/// ```rust,ignore
+/// use restate_worker::{Handler, IpAllowlist};
+///
+/// let handler = Handler::builder(endpoint)
+///     .ip_allowlist(IpAllowlist::from_env(&env)?)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct IpAllowlist {
+    ranges: Vec<IpRange>,
+}
+
+impl IpAllowlist {
+    /// Creates an allowlist from comma-separated CIDR ranges (e.g.
+    /// `192.0.2.0/24, 2001:db8::/32`). Single IP addresses are accepted as
+    /// well.
+    ///
+    /// Fails if a range is invalid or if no range is given.
+    pub fn parse(ranges: &str) -> Result<Self> {
+        let ranges = ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(IpRange::parse)
+            .collect::<Result<Vec<_>>>()?;
+
+        if ranges.is_empty() {
+            return Err(WorkerError::RustError(
+                "IP allowlist does not contain any range".to_owned(),
+            ));
+        }
+
+        Ok(Self { ranges })
+    }
+
+    /// Creates an allowlist from the ranges in [`ALLOWED_IPS_BINDING`].
+    ///
+    /// Fails if the binding is missing or does not contain any valid range,
+    /// so that the allowlist cannot be disabled by accident.
+    pub fn from_env(env: &Env) -> Result<Self> {
+        Self::parse(&bindings::var(env, ALLOWED_IPS_BINDING)?)
+    }
+
+    /// Checks that the client of a request is allowed.
+    pub(crate) fn check(&self, headers: &HeaderMap) -> std::result::Result<(), Error> {
+        let ip = headers
+            .get(CF_CONNECTING_IP)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok())
+            .ok_or_else(|| Error::Forbidden("missing client IP".to_owned()))?;
+
+        if !self.ranges.iter().any(|range| range.contains(ip)) {
+            return Err(Error::Forbidden(format!("client IP {ip} is not allowed")));
+        }
+
+        Ok(())
+    }
+}
+
+/// CIDR range of IP addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    fn parse(range: &str) -> Result<Self> {
+        let invalid = || WorkerError::RustError(format!("invalid IP range '{range}'"));
+
+        let (network, prefix_len) = match range.split_once('/') {
+            Some((network, prefix_len)) => (
+                network.parse::<IpAddr>().map_err(|_| invalid())?,
+                Some(prefix_len.parse::<u8>().map_err(|_| invalid())?),
+            ),
+            None => (range.parse::<IpAddr>().map_err(|_| invalid())?, None),
+        };
+
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        // Clients may be reported as IPv4-mapped IPv6 addresses.
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            ip => ip,
+        };
+
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(allowlist: &IpAllowlist, ip: Option<&str>) -> std::result::Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        if let Some(ip) = ip {
+            headers.insert(CF_CONNECTING_IP, ip.parse().unwrap());
+        }
+
+        allowlist.check(&headers)
+    }
+
+    #[test]
+    fn allows_ips_in_ranges() {
+        let allowlist = IpAllowlist::parse("192.0.2.0/24, 198.51.100.7, 2001:db8::/32,").unwrap();
+
+        for ip in [
+            "192.0.2.1",
+            "192.0.2.255",
+            "198.51.100.7",
+            "2001:db8::1",
+            "::ffff:192.0.2.10",
+        ] {
+            check(&allowlist, Some(ip)).unwrap();
+        }
+
+        for ip in [
+            None,
+            Some("192.0.3.1"),
+            Some("198.51.100.8"),
+            Some("2001:db9::1"),
+            Some("unknown"),
+        ] {
+            let error = check(&allowlist, ip).unwrap_err();
+            assert_eq!(error.status_code(), http::StatusCode::FORBIDDEN, "{ip:?}");
+        }
+
+        check(
+            &IpAllowlist::parse("0.0.0.0/0").unwrap(),
+            Some("203.0.113.1"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        for ranges in [
+            "",
+            " , ",
+            "192.0.2.0/33",
+            "2001:db8::/129",
+            "192.0.2.0/x",
+            "example.com",
+        ] {
+            assert!(IpAllowlist::parse(ranges).is_err(), "{ranges}");
+        }
+    }
+}
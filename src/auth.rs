@@ -0,0 +1,281 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::header::AUTHORIZATION;
+use http::request::Parts;
+use http::{HeaderName, Request};
+use worker::send::SendFuture;
+use worker::{Env, Result};
+
+use crate::Error;
+
+/// Name of the secret holding the tokens accepted by
+/// [`Authenticator::bearer_from_env`] and
+/// [`Authenticator::header_from_env`].
+///
+/// The value is a comma-separated list of tokens. Multiple tokens can be
+/// configured at the same time to rotate them without downtime.
+pub const ENDPOINT_TOKENS_BINDING: &str = "RESTATE_ENDPOINT_TOKENS";
+
+type VerifierFuture = Pin<Box<dyn Future<Output = Result<bool>> + Send>>;
+
+/// Authentication of the requests forwarded to the endpoint.
+///
+/// For deployments that cannot rely on Restate request identity keys (e.g.
+/// older Restate clusters, or requests going through a proxy), the Restate
+/// server can send a token with every request, configured as an additional
+/// header of the deployment. Requests without a valid token are rejected with
+/// `401 Unauthorized` before reaching the endpoint.
+///
+/// ```rust,ignore
+/// use restate_worker::{Authenticator, Handler};
+///
+/// let handler = Handler::builder(endpoint)
+///     .authenticator(Authenticator::bearer_from_env(&env)?)
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct Authenticator(Kind);
+
+#[derive(Clone)]
+enum Kind {
+    Bearer(Vec<String>),
+    Header(HeaderName, Vec<String>),
+    Custom(Arc<dyn Fn(Request<()>) -> VerifierFuture + Send + Sync>),
+}
+
+impl Authenticator {
+    /// Accepts requests with any of the given tokens in the `Authorization:
+    /// Bearer` header.
+    pub fn bearer<T: Into<String>>(tokens: impl IntoIterator<Item = T>) -> Self {
+        Self(Kind::Bearer(tokens.into_iter().map(Into::into).collect()))
+    }
+
+    /// Accepts requests with any of the tokens in [`ENDPOINT_TOKENS_BINDING`]
+    /// in the `Authorization: Bearer` header.
+    ///
+    /// Fails if the binding is missing or does not contain any token, so that
+    /// authentication cannot be disabled by accident.
+    pub fn bearer_from_env(env: &Env) -> Result<Self> {
+        Ok(Self::bearer(tokens_from_env(env)?))
+    }
+
+    /// Accepts requests with any of the given shared secrets in `header`.
+    pub fn header<T: Into<String>>(
+        header: HeaderName,
+        secrets: impl IntoIterator<Item = T>,
+    ) -> Self {
+        Self(Kind::Header(
+            header,
+            secrets.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Accepts requests with any of the tokens in [`ENDPOINT_TOKENS_BINDING`]
+    /// in `header`.
+    ///
+    /// Fails if the binding is missing or does not contain any token.
+    pub fn header_from_env(env: &Env, header: HeaderName) -> Result<Self> {
+        Ok(Self::header(header, tokens_from_env(env)?))
+    }
+
+    /// Accepts requests for which `verifier` resolves to `true`.
+    ///
+    /// The verifier receives the request without its body. It may call
+    /// JavaScript APIs (e.g. to look up a token in KV); failures are
+    /// answered with `503 Service Unavailable`.
+    ///
+    /// ```rust,ignore
+    /// let authenticator = Authenticator::custom(move |req| {
+    ///     let kv = kv.clone();
+    ///     async move {
+    ///         let Some(token) = req.headers().get("x-api-key").and_then(|v| v.to_str().ok()) else {
+    ///             return Ok(false);
+    ///         };
+    ///         Ok(kv.get(token).text().await?.is_some())
+    ///     }
+    /// });
+    /// ```
+    pub fn custom<F, Fut>(verifier: F) -> Self
+    where
+        F: Fn(Request<()>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<bool>> + 'static,
+    {
+        // JavaScript futures are not Send, but Workers isolates are single-threaded.
+        Self(Kind::Custom(Arc::new(move |req| {
+            Box::pin(SendFuture::new(verifier(req)))
+        })))
+    }
+
+    /// Checks that a request is authenticated.
+    pub(crate) async fn authenticate(&self, parts: &Parts) -> std::result::Result<(), Error> {
+        match &self.0 {
+            Kind::Bearer(tokens) => {
+                let token = parts
+                    .headers
+                    .get(AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(bearer_token)
+                    .ok_or_else(|| Error::Unauthorized("missing bearer token".to_owned()))?;
+
+                check(tokens, token, "invalid bearer token")
+            }
+            Kind::Header(name, secrets) => {
+                let secret = parts
+                    .headers
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or_else(|| Error::Unauthorized(format!("missing '{name}' header")))?;
+
+                check(secrets, secret, "invalid shared secret")
+            }
+            Kind::Custom(verifier) => {
+                let mut req = Request::builder()
+                    .method(parts.method.clone())
+                    .uri(parts.uri.clone())
+                    .version(parts.version)
+                    .body(())
+                    .expect("request parts must be valid");
+                *req.headers_mut() = parts.headers.clone();
+
+                match verifier(req).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(Error::Unauthorized("request rejected".to_owned())),
+                    Err(e) => Err(Error::Unavailable(format!(
+                        "cannot authenticate request: {e}"
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+/// Extracts the token of an `Authorization: Bearer` header value.
+///
+/// The scheme is case-insensitive, as required by RFC 7235.
+fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.trim().split_once(' ')?;
+    let token = token.trim();
+
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Reads the comma-separated tokens in [`ENDPOINT_TOKENS_BINDING`].
+fn tokens_from_env(env: &Env) -> Result<Vec<String>> {
+    let value = env.secret(ENDPOINT_TOKENS_BINDING).map_err(|e| {
+        worker::Error::RustError(format!(
+            "cannot read endpoint tokens from '{ENDPOINT_TOKENS_BINDING}': {e}"
+        ))
+    })?;
+
+    let tokens = parse_tokens(&value.to_string());
+    if tokens.is_empty() {
+        return Err(worker::Error::RustError(format!(
+            "no endpoint tokens configured in '{ENDPOINT_TOKENS_BINDING}'"
+        )));
+    }
+
+    Ok(tokens)
+}
+
+fn parse_tokens(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Checks that `token` is one of the accepted tokens.
+fn check(accepted: &[String], token: &str, message: &str) -> std::result::Result<(), Error> {
+    // Every token is compared, in constant time, so that timing does not reveal them.
+    let valid = accepted.iter().fold(false, |valid, accepted| {
+        constant_time_eq(accepted.as_bytes(), token.as_bytes()) | valid
+    });
+
+    if !valid {
+        return Err(Error::Unauthorized(message.to_owned()));
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn authenticate(
+        authenticator: &Authenticator,
+        header: Option<(&str, &str)>,
+    ) -> std::result::Result<(), Error> {
+        let mut req = Request::get("/discover");
+        if let Some((name, value)) = header {
+            req = req.header(name, value);
+        }
+        let (parts, ()) = req.body(()).unwrap().into_parts();
+
+        block_on(authenticator.authenticate(&parts))
+    }
+
+    #[test]
+    fn accepts_rotated_tokens() {
+        let authenticator = Authenticator::bearer(parse_tokens("current, previous,"));
+
+        for header in [
+            "Bearer current",
+            "Bearer previous",
+            "bearer current",
+            "BEARER  current ",
+        ] {
+            authenticate(&authenticator, Some(("authorization", header))).unwrap();
+        }
+
+        for header in [
+            None,
+            Some(("authorization", "Bearer other")),
+            Some(("authorization", "Basic current")),
+            Some(("authorization", "Bearer")),
+            Some(("authorization", "Bearercurrent")),
+            Some(("x-token", "current")),
+        ] {
+            let error = authenticate(&authenticator, header).unwrap_err();
+            assert!(matches!(error, Error::Unauthorized(_)), "{error}");
+        }
+    }
+
+    #[test]
+    fn accepts_shared_secret_header() {
+        let authenticator =
+            Authenticator::header(HeaderName::from_static("x-restate-secret"), ["secret"]);
+
+        authenticate(&authenticator, Some(("x-restate-secret", "secret"))).unwrap();
+        assert!(authenticate(&authenticator, Some(("x-restate-secret", "secreT"))).is_err());
+        assert!(authenticate(&authenticator, None).is_err());
+    }
+
+    #[test]
+    fn runs_custom_verifier() {
+        let authenticator = Authenticator::custom(|req| async move {
+            match req.headers().get("x-api-key") {
+                Some(key) if key == "fail" => Err(worker::Error::RustError("KV down".to_owned())),
+                Some(key) => Ok(key == "valid"),
+                None => Ok(false),
+            }
+        });
+
+        authenticate(&authenticator, Some(("x-api-key", "valid"))).unwrap();
+
+        let error = authenticate(&authenticator, Some(("x-api-key", "other"))).unwrap_err();
+        assert_eq!(error.status_code(), http::StatusCode::UNAUTHORIZED);
+
+        let error = authenticate(&authenticator, Some(("x-api-key", "fail"))).unwrap_err();
+        assert_eq!(error.status_code(), http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
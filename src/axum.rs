@@ -0,0 +1,111 @@
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use ::axum::body::Body;
+use ::axum::response::Response;
+use http::Request;
+use tower_service::Service;
+use worker::Env;
+
+use crate::Handler;
+use crate::bindings::Bindings;
+
+/// [`Handler`] adapted to serve requests of an [axum](https://docs.rs/axum)
+/// application.
+///
+/// Created with [`Handler::into_axum`]. Mount it in a router to serve the
+/// Restate endpoint next to the rest of the application:
+///
+/// ```rust,ignore
+/// use axum::Router;
+///
+/// let router = Router::new()
+///     .route("/", get(index))
+///     .nest_service("/restate", handler.into_axum());
+/// ```
+///
+/// Failures are turned into error responses, as axum services cannot fail.
+#[derive(Clone)]
+pub struct AxumService {
+    handler: Handler,
+    bindings: Option<Arc<Bindings>>,
+}
+
+impl AxumService {
+    /// Exposes the worker bindings of the current request to the invoked
+    /// Restate handlers.
+    ///
+    /// See [`Handler::handle_with_env`] for details.
+    pub fn with_env(mut self, env: Env, ctx: worker::Context) -> Self {
+        self.bindings = Some(Arc::new(Bindings::new(env, ctx)));
+        self
+    }
+}
+
+impl Handler {
+    /// Converts the handler into a service that can be mounted in an axum
+    /// router.
+    pub fn into_axum(self) -> AxumService {
+        AxumService {
+            handler: self,
+            bindings: None,
+        }
+    }
+}
+
+impl Service<Request<Body>> for AxumService {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let Self { handler, bindings } = self.clone();
+
+        Box::pin(async move {
+            let response = handler.serve(req, bindings).await;
+
+            Ok(response.map(Body::new))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::axum::Router;
+    use http::StatusCode;
+    use http_body_util::BodyExt;
+    use restate_sdk::prelude::Endpoint;
+    use tower::ServiceExt;
+
+    fn call(router: Router, uri: &str) -> (StatusCode, String) {
+        let req = Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = futures::executor::block_on(router.oneshot(req)).unwrap();
+        let status = response.status();
+        let body = futures::executor::block_on(response.into_body().collect())
+            .unwrap()
+            .to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn nested_in_router() {
+        let handler = Handler::new(Endpoint::builder().build());
+        let router = Router::new().nest_service("/restate", handler.into_axum());
+
+        let (status, body) = call(router.clone(), "/restate/discover");
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("REQUEST_RESPONSE"), "{body}");
+
+        let (status, _) = call(router, "/discover");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use restate_sdk::context::ContextSideEffects;
+use worker::{Env, Error, Result};
+
+/// Reads the variable `name` from the worker environment.
+pub(crate) fn var(env: &Env, name: &str) -> Result<String> {
+    env.var(name)
+        .map(|value| value.to_string())
+        .map_err(|e| Error::RustError(format!("cannot read '{name}': {e}")))
+}
+
+/// Cloudflare bindings of the request currently being served.
+pub(crate) struct Bindings {
+    env: Env,
+    ctx: Option<Arc<worker::Context>>,
+}
+
+impl Bindings {
+    pub(crate) fn new(env: Env, ctx: worker::Context) -> Self {
+        Self {
+            env,
+            ctx: Some(Arc::new(ctx)),
+        }
+    }
+
+    /// Bindings without an execution context, as [`worker::Router`] routes
+    /// only receive the environment.
+    pub(crate) fn from_env(env: Env) -> Self {
+        Self { env, ctx: None }
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Bindings>>> = const { RefCell::new(None) };
+}
+
+/// Returns the bindings of the request currently being served, if any.
+fn current() -> Option<Arc<Bindings>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Makes `bindings` the current bindings until the guard is dropped.
+fn enter(bindings: Arc<Bindings>) -> Guard {
+    Guard(CURRENT.with(|current| current.replace(Some(bindings))))
+}
+
+/// Restores the previously current bindings when dropped.
+struct Guard(Option<Arc<Bindings>>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+pin_project! {
+    /// Response body that makes the request bindings available while it is
+    /// being polled.
+    ///
+    /// Restate invocations run lazily as the response body is pulled, so the
+    /// bindings have to be in scope for each poll rather than for the call
+    /// to [`Handler::handle`](crate::Handler::handle).
+    pub(crate) struct Scoped<B> {
+        #[pin]
+        inner: B,
+        bindings: Option<Arc<Bindings>>,
+    }
+}
+
+impl<B> Scoped<B> {
+    pub(crate) fn new(inner: B, bindings: Option<Arc<Bindings>>) -> Self {
+        Self { inner, bindings }
+    }
+}
+
+impl<B: Body> Body for Scoped<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let _guard = this
+            .bindings
+            .as_ref()
+            .map(|bindings| enter(Arc::clone(bindings)));
+
+        this.inner.poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Gives Restate handlers access to the Cloudflare bindings of the current
+/// request.
+///
+/// The bindings are only available to handlers invoked through
+/// [`Handler::handle_with_env`](crate::Handler::handle_with_env) (which the
+/// [`main`](crate::main) macro uses) or mounted with
+/// [`RouterExt::restate`](crate::RouterExt::restate), which only exposes the
+/// environment.
+///
+/// ```rust,ignore
+/// use restate_sdk::prelude::*;
+/// use restate_worker::WorkerContextExt;
+///
+/// impl Greeter for GreeterImpl {
+///     async fn greet(&self, ctx: Context<'_>, name: String) -> HandlerResult<String> {
+///         let greeting = ctx.worker_env()?.var("GREETING")?.to_string();
+///
+///         Ok(format!("{greeting} {name}"))
+///     }
+/// }
+/// ```
+pub trait WorkerContextExt<'ctx>: ContextSideEffects<'ctx> {
+    /// Returns the worker environment (KV, D1, R2, secrets, etc.) of the
+    /// current request.
+    fn worker_env(&self) -> Result<Env> {
+        current()
+            .map(|bindings| bindings.env.clone())
+            .ok_or_else(unavailable)
+    }
+
+    /// Returns the worker execution context of the current request.
+    fn worker_context(&self) -> Result<Arc<worker::Context>> {
+        let bindings = current().ok_or_else(unavailable)?;
+
+        bindings.ctx.clone().ok_or_else(|| {
+            Error::RustError(
+                "worker execution context is not available to handlers mounted on a worker::Router"
+                    .to_owned(),
+            )
+        })
+    }
+}
+
+impl<'ctx, C: ContextSideEffects<'ctx>> WorkerContextExt<'ctx> for C {}
+
+fn unavailable() -> Error {
+    Error::RustError(
+        "worker bindings are not available: serve requests with Handler::handle_with_env"
+            .to_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use http_body_util::BodyExt;
+    use worker::wasm_bindgen::{JsCast, JsValue};
+
+    // Placeholder bindings: they must not be cloned, as that would call into JavaScript.
+    fn bindings() -> Arc<Bindings> {
+        let env = JsValue::UNDEFINED.unchecked_into();
+        let ctx = worker::Context::new(JsValue::UNDEFINED.unchecked_into());
+
+        Arc::new(Bindings::new(env, ctx))
+    }
+
+    fn is_current(bindings: &Arc<Bindings>) -> bool {
+        current().is_some_and(|current| Arc::ptr_eq(&current, bindings))
+    }
+
+    #[test]
+    fn bindings_scoped_to_poll() {
+        let outer = bindings();
+        let inner = bindings();
+
+        assert!(current().is_none());
+
+        {
+            let _outer = enter(Arc::clone(&outer));
+            {
+                let _inner = enter(Arc::clone(&inner));
+                assert!(is_current(&inner));
+            }
+            assert!(is_current(&outer));
+        }
+
+        assert!(current().is_none());
+    }
+
+    /// Body yielding a single frame that records whether the expected bindings were current.
+    struct Probe(Arc<Bindings>);
+
+    impl Body for Probe {
+        type Data = Bytes;
+        type Error = std::convert::Infallible;
+
+        fn poll_frame(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<std::result::Result<Frame<Bytes>, Self::Error>>> {
+            let seen = if is_current(&self.0) {
+                "in scope"
+            } else {
+                "out of scope"
+            };
+
+            Poll::Ready(Some(Ok(Frame::data(Bytes::from_static(seen.as_bytes())))))
+        }
+    }
+
+    #[test]
+    fn scoped_body_polls_with_bindings() {
+        let bindings = bindings();
+        let mut body = Scoped::new(Probe(Arc::clone(&bindings)), Some(bindings));
+
+        let frame = futures::executor::block_on(body.frame()).unwrap().unwrap();
+
+        assert_eq!(frame.into_data().unwrap(), "in scope");
+        assert!(current().is_none());
+    }
+}
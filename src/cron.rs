@@ -0,0 +1,296 @@
+use serde_json::Value;
+use worker::{Error, ScheduledEvent};
+
+use crate::ingress::{IngressClient, IngressError, IngressTransport, SendResponse, Transport};
+
+/// Placeholder replaced by the cron expression of the event.
+const CRON_PLACEHOLDER: &str = "{{cron}}";
+
+/// Placeholder replaced by the scheduled time of the event, in milliseconds
+/// since the Unix epoch.
+const TIME_PLACEHOLDER: &str = "{{scheduledTime}}";
+
+/// Placeholder replaced by the scheduled time of the event, formatted as an
+/// RFC 3339 UTC timestamp (e.g. `2024-01-01T00:00:00Z`).
+const DATE_PLACEHOLDER: &str = "{{scheduledDate}}";
+
+/// Invocation of a Restate handler triggered by a cron expression.
+///
+/// The object key and the payload are templates: the `{{cron}}`,
+/// `{{scheduledTime}}` (milliseconds since the Unix epoch) and
+/// `{{scheduledDate}}` (RFC 3339 timestamp) placeholders are replaced with the
+/// values of the scheduled event. A payload string consisting of
+/// `{{scheduledTime}}` only is replaced with a number.
+#[derive(Debug, Clone)]
+pub struct CronTrigger {
+    cron: String,
+    service: String,
+    key: Option<String>,
+    handler: String,
+    payload: Value,
+}
+
+impl CronTrigger {
+    /// Creates a trigger invoking `handler` of `service` without input when
+    /// the `cron` expression fires.
+    ///
+    /// The expression must match the one configured in the worker triggers
+    /// exactly.
+    pub fn new(
+        cron: impl Into<String>,
+        service: impl Into<String>,
+        handler: impl Into<String>,
+    ) -> Self {
+        Self {
+            cron: cron.into(),
+            service: service.into(),
+            key: None,
+            handler: handler.into(),
+            payload: Value::Null,
+        }
+    }
+
+    /// Invokes the handler of the virtual object (or workflow) with the given
+    /// key.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sends the given payload template as handler input.
+    pub fn payload(mut self, template: Value) -> Self {
+        self.payload = template;
+        self
+    }
+
+    async fn invoke<T: Transport>(
+        &self,
+        client: &IngressClient<T>,
+        schedule: &Schedule<'_>,
+    ) -> Result<SendResponse, IngressError> {
+        let key = self.key.as_deref().map(|key| schedule.render_str(key));
+        let target = match &key {
+            Some(key) => client.object(&self.service, key),
+            None => client.service(&self.service),
+        };
+
+        target
+            .handler(&self.handler)
+            .idempotency_key(&self.idempotency_key(key.as_deref(), schedule))
+            .send(&schedule.render(&self.payload))
+            .await
+    }
+
+    /// Derives the idempotency key of the invocation from the scheduled time,
+    /// so that retries of the same event invoke the handler only once.
+    fn idempotency_key(&self, key: Option<&str>, schedule: &Schedule<'_>) -> String {
+        let target = match key {
+            Some(key) => format!("{}/{key}/{}", self.service, self.handler),
+            None => format!("{}/{}", self.service, self.handler),
+        };
+
+        format!("cron:{}:{}:{target}", schedule.cron, schedule.time)
+    }
+}
+
+/// Maps cron triggers of the worker to Restate handler invocations.
+///
+/// Invocations are sent through the Restate ingress without waiting for their
+/// output, with an idempotency key derived from the scheduled time.
+///
+/// ```rust,ignore
+/// use restate_worker::{CronTrigger, CronTriggers};
+/// use restate_worker::ingress::IngressClient;
+///
+/// #[event(scheduled)]
+/// async fn scheduled(event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+///     let result = match IngressClient::from_env(&env) {
+///         Ok(client) => CronTriggers::new(client)
+///             .trigger(CronTrigger::new("*/5 * * * *", "Cleanup", "run"))
+///             .trigger(
+///                 CronTrigger::new("0 0 * * *", "Report", "run")
+///                     .key("daily-{{scheduledDate}}")
+///                     .payload(json!({ "until": "{{scheduledTime}}" })),
+///             )
+///             .run(&event)
+///             .await,
+///         Err(e) => Err(e),
+///     };
+///
+///     if let Err(e) = result {
+///         console_error!("cannot invoke Restate handlers: {e}");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CronTriggers<T = IngressTransport> {
+    client: IngressClient<T>,
+    triggers: Vec<CronTrigger>,
+}
+
+impl<T: Transport> CronTriggers<T> {
+    /// Creates an empty mapping sending invocations with `client`.
+    pub fn new(client: IngressClient<T>) -> Self {
+        Self {
+            client,
+            triggers: Vec::new(),
+        }
+    }
+
+    /// Adds a trigger. Several triggers may share the same cron expression.
+    pub fn trigger(mut self, trigger: CronTrigger) -> Self {
+        self.triggers.push(trigger);
+        self
+    }
+
+    /// Sends the invocations mapped to the cron expression of the event.
+    ///
+    /// Fails if no invocation is mapped to the expression.
+    pub async fn run(&self, event: &ScheduledEvent) -> worker::Result<()> {
+        let cron = event.cron();
+        if !self.triggers.iter().any(|trigger| trigger.cron == cron) {
+            return Err(Error::RustError(format!(
+                "no Restate invocation is mapped to cron '{cron}'"
+            )));
+        }
+
+        self.dispatch(&cron, event.schedule() as u64).await?;
+        Ok(())
+    }
+
+    /// Sends the invocations mapped to `cron`, scheduled at `scheduled_time`
+    /// (in milliseconds since the Unix epoch).
+    ///
+    /// Stops at the first invocation rejected by the ingress. Retrying is
+    /// safe: the invocations sent before are deduplicated by Restate.
+    pub async fn dispatch(
+        &self,
+        cron: &str,
+        scheduled_time: u64,
+    ) -> Result<Vec<SendResponse>, IngressError> {
+        let schedule = Schedule::new(cron, scheduled_time);
+        let mut responses = Vec::new();
+
+        for trigger in self.triggers.iter().filter(|trigger| trigger.cron == cron) {
+            responses.push(trigger.invoke(&self.client, &schedule).await?);
+        }
+
+        Ok(responses)
+    }
+}
+
+/// Values of a scheduled event substituted in templates.
+struct Schedule<'a> {
+    cron: &'a str,
+    time: u64,
+    date: String,
+}
+
+impl<'a> Schedule<'a> {
+    fn new(cron: &'a str, time: u64) -> Self {
+        Self {
+            cron,
+            time,
+            date: rfc3339(time / 1000),
+        }
+    }
+
+    fn render(&self, template: &Value) -> Value {
+        match template {
+            Value::String(s) if s == TIME_PLACEHOLDER => Value::from(self.time),
+            Value::String(s) => Value::String(self.render_str(s)),
+            Value::Array(items) => items.iter().map(|item| self.render(item)).collect(),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), self.render(value)))
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    fn render_str(&self, template: &str) -> String {
+        template
+            .replace(CRON_PLACEHOLDER, self.cron)
+            .replace(TIME_PLACEHOLDER, &self.time.to_string())
+            .replace(DATE_PLACEHOLDER, &self.date)
+    }
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+fn rfc3339(secs: u64) -> String {
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Converts days since the epoch to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingress::MockIngress;
+    use futures::executor::block_on;
+    use serde_json::json;
+
+    #[test]
+    fn dispatches_matching_triggers() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let triggers = CronTriggers::new(IngressClient::with_transport("http://restate", &ingress))
+            .trigger(CronTrigger::new("*/5 * * * *", "Cleanup", "run"))
+            .trigger(
+                CronTrigger::new("0 0 * * *", "Report", "run")
+                    .key("daily-{{scheduledDate}}")
+                    .payload(json!({
+                        "cron": "{{cron}}",
+                        "until": "{{scheduledTime}}",
+                        "tags": ["at {{scheduledDate}}", 1],
+                    })),
+            );
+
+        // 2024-02-29T00:00:00Z
+        let responses = block_on(triggers.dispatch("0 0 * * *", 1_709_164_800_000)).unwrap();
+        assert_eq!(responses.len(), 1);
+
+        let request = &ingress.requests()[0];
+        assert_eq!(
+            request.uri(),
+            "http://restate/Report/daily-2024-02-29T00%3A00%3A00Z/run/send"
+        );
+        assert_eq!(
+            request.headers()["idempotency-key"],
+            "cron:0 0 * * *:1709164800000:Report/daily-2024-02-29T00:00:00Z/run"
+        );
+        assert_eq!(
+            serde_json::from_slice::<Value>(request.body()).unwrap(),
+            json!({
+                "cron": "0 0 * * *",
+                "until": 1_709_164_800_000u64,
+                "tags": ["at 2024-02-29T00:00:00Z", 1],
+            })
+        );
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_868_799), "2000-02-29T23:59:59Z");
+        assert_eq!(rfc3339(1_735_689_600), "2025-01-01T00:00:00Z");
+    }
+}
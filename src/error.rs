@@ -0,0 +1,125 @@
+use bytes::Bytes;
+use http::header::CONTENT_TYPE;
+use http::{HeaderName, HeaderValue, Response, StatusCode};
+use http_body_util::Full;
+
+/// Header identifying the adapter in responses it generates itself, mirroring
+/// the `x-restate-server` header set by the Restate SDK.
+const X_RESTATE_SERVER: HeaderName = HeaderName::from_static("x-restate-server");
+const X_RESTATE_SERVER_VALUE: HeaderValue =
+    HeaderValue::from_static(concat!("restate-worker/", env!("CARGO_PKG_VERSION")));
+
+/// Errors returned by [`Handler`](crate::Handler) when a request cannot be
+/// forwarded to the Restate endpoint.
+///
+/// Each error maps to an HTTP status code, so that the Restate server can
+/// tell apart requests that should not be retried (4xx) from transient
+/// failures (5xx).
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The request path is not served by the handler.
+    #[error("path '{0}' is not served by this handler")]
+    NotFound(String),
+
+    /// The request is not authenticated.
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The client of the request is not allowed to reach the endpoint.
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
+    /// The request cannot be authenticated at the moment (e.g. the keys
+    /// validating it cannot be fetched).
+    #[error("{0}")]
+    Unavailable(String),
+
+    /// The request targets another version of the worker than the running
+    /// one.
+    #[error("worker version '{requested}' is not served by this deployment (running '{current}')")]
+    VersionMismatch {
+        /// Version requested in the URL.
+        requested: String,
+        /// Version of the running worker.
+        current: String,
+    },
+
+    /// The request body exceeds the configured limit.
+    #[error("request body exceeds the limit of {0} bytes")]
+    PayloadTooLarge(usize),
+
+    /// The Restate endpoint failed to produce the response body.
+    #[error("cannot produce response body: {0}")]
+    Invocation(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    /// A Restate handler panicked while processing the request.
+    ///
+    /// Holds the panic message along with its location. Only reported when
+    /// [`HandlerBuilder::catch_panics`](crate::HandlerBuilder::catch_panics)
+    /// is enabled.
+    #[error("invocation {0}")]
+    Panic(String),
+
+    /// The response body cannot be converted into a Workers body.
+    #[error("cannot convert response body: {0}")]
+    Body(#[source] worker::Error),
+}
+
+impl Error {
+    /// Returns the HTTP status code for this error.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Error::VersionMismatch { .. } => StatusCode::MISDIRECTED_REQUEST,
+            Error::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::Invocation(_) | Error::Panic(_) | Error::Body(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Converts the error into a response in the format used by the Restate
+    /// SDK: the status code of the error and its message as plain text.
+    pub(crate) fn to_response(&self) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(self.status_code())
+            .header(X_RESTATE_SERVER, X_RESTATE_SERVER_VALUE)
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Full::new(Bytes::from(self.to_string())))
+            .expect("headers must be valid")
+    }
+}
+
+impl From<Error> for worker::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Body(e) => e,
+            e => worker::Error::RustError(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[test]
+    fn error_response() {
+        let response = Error::PayloadTooLarge(16).to_response();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/plain");
+        assert!(response.headers().contains_key(X_RESTATE_SERVER));
+
+        let body = futures::executor::block_on(response.into_body().collect()).unwrap();
+        assert_eq!(
+            body.to_bytes(),
+            "request body exceeds the limit of 16 bytes"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::pin::Pin;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use restate_sdk::context::RunClosure;
+use restate_sdk::errors::HandlerError;
+use worker::js_sys::Uint8Array;
+use worker::send::SendFuture;
+use worker::{Fetch, Headers, Request, RequestInit};
+
+/// Outbound HTTP request executed as a Restate side effect.
+///
+/// `JournaledFetch` is a [`RunClosure`]: pass it to
+/// [`ctx.run`](restate_sdk::context::ContextSideEffects::run) to send the
+/// request with the Workers [`Fetch`] API and record the response (status,
+/// headers and body) in the journal. When the invocation is replayed, the
+/// journaled response is returned instead of sending the request again.
+///
+/// Failures to send the request are retried according to the retry policy of
+/// the `run` block (see [`RunFuture::retry_policy`](restate_sdk::context::RunFuture::retry_policy)).
+///
+/// ```rust,ignore
+/// use restate_sdk::prelude::*;
+/// use restate_worker::JournaledFetch;
+///
+/// let request = JournaledFetch::post("https://api.example.com/orders")
+///     .json(&order)?
+///     .retry_on_status(|status| status.is_server_error());
+///
+/// let response = ctx
+///     .run(request)
+///     .name("create order")
+///     .retry_policy(RunRetryPolicy::default().max_attempts(5))
+///     .await?;
+///
+/// let receipt: Receipt = response.json()?;
+/// ```
+#[derive(Debug)]
+pub struct JournaledFetch {
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+    body: Bytes,
+    retry_on_status: Option<fn(StatusCode) -> bool>,
+}
+
+impl JournaledFetch {
+    /// Creates a new request with the given method and URL.
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            retry_on_status: None,
+        }
+    }
+
+    /// Creates a new `GET` request.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    /// Creates a new `POST` request.
+    pub fn post(url: impl Into<String>) -> Self {
+        Self::new(Method::POST, url)
+    }
+
+    /// Creates a new `PUT` request.
+    pub fn put(url: impl Into<String>) -> Self {
+        Self::new(Method::PUT, url)
+    }
+
+    /// Creates a new `DELETE` request.
+    pub fn delete(url: impl Into<String>) -> Self {
+        Self::new(Method::DELETE, url)
+    }
+
+    /// Appends a header to the request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Sets the request body.
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sets a JSON request body and the matching `Content-Type` header.
+    pub fn json<T: serde::Serialize>(self, value: &T) -> serde_json::Result<Self> {
+        let body = serde_json::to_vec(value)?;
+
+        Ok(self
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(body))
+    }
+
+    /// Treats responses with a matching status as failures to be retried
+    /// instead of journaling them.
+    ///
+    /// By default, every response is journaled regardless of its status.
+    pub fn retry_on_status(mut self, retry: fn(StatusCode) -> bool) -> Self {
+        self.retry_on_status = Some(retry);
+        self
+    }
+}
+
+impl RunClosure for JournaledFetch {
+    type Output = JournaledResponse;
+    type Fut = FetchFuture;
+
+    fn run(self) -> Self::Fut {
+        let Self {
+            method,
+            url,
+            headers,
+            body,
+            retry_on_status,
+        } = self;
+
+        // JavaScript futures are not Send, but Workers isolates are single-threaded.
+        Box::pin(SendFuture::new(async move {
+            let response = fetch(method, &url, &headers, &body).await?;
+
+            if retry_on_status.is_some_and(|retry| retry(response.status())) {
+                return Err(HandlerError::from(format!(
+                    "request to {url} failed with status {}",
+                    response.status()
+                )));
+            }
+
+            Ok(response)
+        }))
+    }
+}
+
+type FetchFuture = Pin<Box<dyn Future<Output = Result<JournaledResponse, HandlerError>> + Send>>;
+
+/// Sends a request with the Workers [`Fetch`] API and buffers the response.
+pub(crate) async fn fetch(
+    method: Method,
+    url: &str,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> worker::Result<JournaledResponse> {
+    let mut init = RequestInit::new();
+    init.with_method(method.as_str().to_owned().into())
+        .with_headers(Headers::from(headers));
+
+    if !body.is_empty() {
+        init.with_body(Some(Uint8Array::from(body.as_ref()).into()));
+    }
+
+    let request = Request::new_with_init(url, &init)?;
+    let mut response = Fetch::Request(request).send().await?;
+
+    let status = StatusCode::from_u16(response.status_code())
+        .map_err(|e| worker::Error::RustError(format!("invalid response status: {e}")))?;
+    let headers = HeaderMap::from(response.headers());
+    let body = response.bytes().await?;
+
+    Ok(JournaledResponse::new(status, headers, body.into()))
+}
+
+/// HTTP response recorded in the Restate journal by [`JournaledFetch`].
+#[derive(Debug, Clone)]
+pub struct JournaledResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl JournaledResponse {
+    pub(crate) fn new(status: StatusCode, headers: HeaderMap, body: Bytes) -> Self {
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Returns the response status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the response headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the response body.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Returns the response body as text.
+    pub fn text(&self) -> Result<String, std::string::FromUtf8Error> {
+        String::from_utf8(self.body.to_vec())
+    }
+
+    /// Deserializes the response body as JSON.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+impl From<JournaledResponse> for http::Response<Bytes> {
+    fn from(response: JournaledResponse) -> Self {
+        let mut res = http::Response::new(response.body);
+        *res.status_mut() = response.status;
+        *res.headers_mut() = response.headers;
+        res
+    }
+}
+
+/// Journal representation of [`JournaledResponse`].
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl restate_sdk::serde::Serialize for JournaledResponse {
+    type Error = serde_json::Error;
+
+    fn serialize(&self) -> Result<Bytes, Self::Error> {
+        let entry = Entry {
+            status: self.status.as_u16(),
+            headers: self
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect(),
+            body: BASE64.encode(&self.body),
+        };
+
+        serde_json::to_vec(&entry).map(Bytes::from)
+    }
+}
+
+impl restate_sdk::serde::Deserialize for JournaledResponse {
+    type Error = serde_json::Error;
+
+    fn deserialize(bytes: &mut Bytes) -> Result<Self, Self::Error> {
+        use serde::de::Error;
+
+        let entry: Entry = serde_json::from_slice(bytes)?;
+        let status = StatusCode::from_u16(entry.status).map_err(serde_json::Error::custom)?;
+
+        let mut headers = HeaderMap::with_capacity(entry.headers.len());
+        for (name, value) in entry.headers {
+            let name = HeaderName::try_from(name).map_err(serde_json::Error::custom)?;
+            let value = HeaderValue::try_from(value).map_err(serde_json::Error::custom)?;
+            headers.append(name, value);
+        }
+
+        let body = BASE64
+            .decode(entry.body)
+            .map_err(serde_json::Error::custom)?;
+
+        Ok(Self::new(status, headers, body.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restate_sdk::serde::{Deserialize, Serialize};
+
+    #[test]
+    fn journal_roundtrip() {
+        let mut headers = HeaderMap::new();
+        headers.append("set-cookie", HeaderValue::from_static("a=1"));
+        headers.append("set-cookie", HeaderValue::from_static("b=2"));
+        let response = JournaledResponse::new(
+            StatusCode::CREATED,
+            headers,
+            Bytes::from_static(&[0, 159, 146]),
+        );
+
+        let mut bytes = response.serialize().unwrap();
+        let decoded = JournaledResponse::deserialize(&mut bytes).unwrap();
+
+        assert_eq!(decoded.status(), StatusCode::CREATED);
+        assert_eq!(decoded.headers().get_all("set-cookie").iter().count(), 2);
+        assert_eq!(decoded.body(), response.body());
+    }
+
+    #[test]
+    fn rejects_invalid_journaled_status() {
+        let mut bytes = Bytes::from_static(br#"{"status": 1000, "headers": [], "body": ""}"#);
+
+        assert!(JournaledResponse::deserialize(&mut bytes).is_err());
+    }
+
+    #[test]
+    fn json_request_body() {
+        let fetch = JournaledFetch::post("https://example.com")
+            .json(&serde_json::json!({ "id": 1 }))
+            .unwrap();
+
+        assert_eq!(fetch.headers[CONTENT_TYPE], "application/json");
+        assert_eq!(fetch.body, r#"{"id":1}"#);
+    }
+}
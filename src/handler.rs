@@ -0,0 +1,855 @@
+use bytes::Bytes;
+use http::header::CONTENT_LENGTH;
+use http::request::Parts;
+use http::uri::PathAndQuery;
+use http::{HeaderMap, HeaderName, HeaderValue, Request, Response, Uri};
+use http_body_util::{BodyExt, Either, Full, Limited};
+use restate_sdk::endpoint;
+use restate_sdk::prelude::{Endpoint, HandleOptions, ProtocolMode};
+use std::sync::Arc;
+
+use worker::{Body, Env, Result, console_error};
+
+use crate::Error;
+use crate::access::CloudflareAccess;
+use crate::allowlist::IpAllowlist;
+use crate::auth::Authenticator;
+use crate::bindings::{Bindings, Scoped};
+use crate::identity;
+use crate::panic;
+use crate::version::{self, Pinned};
+
+pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Response body produced by [`Handler`] before conversion into a Workers [`Body`].
+///
+/// Responses generated by the adapter itself are buffered, as are responses
+/// produced by the Restate endpoint when panics are caught. Otherwise, the
+/// endpoint response is streamed.
+pub(crate) type ResponseBody = Either<Full<Bytes>, Scoped<endpoint::ResponseBody>>;
+
+/// HTTP handler that forwards requests to a Restate [`Endpoint`].
+///
+/// Wraps a Restate endpoint and adapts it to the Cloudflare Workers runtime.
+/// Requests are processed using [`ProtocolMode::RequestResponse`] by default
+/// because Cloudflare Workers buffer the entire request body before passing it
+/// to the worker, making bidirectional streaming impossible.
+///
+/// Use [`Handler::builder`] to customize how requests are forwarded.
+///
+/// Handlers are cheap to clone: clones share the same endpoint and options.
+#[derive(Clone)]
+pub struct Handler {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    endpoint: Endpoint,
+    protocol_mode: ProtocolMode,
+    path_prefix: Option<String>,
+    version_id: Option<String>,
+    ip_allowlist: Option<IpAllowlist>,
+    access: Option<CloudflareAccess>,
+    authenticator: Option<Authenticator>,
+    max_request_body_size: Option<usize>,
+    removed_request_headers: Vec<HeaderName>,
+    response_headers: HeaderMap,
+    respond_with_errors: bool,
+    catch_panics: bool,
+}
+
+impl Handler {
+    /// Creates a new handler backed by the given Restate endpoint.
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self::builder(endpoint).build()
+    }
+
+    /// Returns a builder for a handler backed by the given Restate endpoint.
+    pub fn builder(endpoint: Endpoint) -> HandlerBuilder {
+        HandlerBuilder::new(endpoint)
+    }
+
+    /// Creates a new handler with request identity verification enabled.
+    ///
+    /// See [`HandlerBuilder::from_env`] for details.
+    pub fn from_env(env: &Env, endpoint: endpoint::Builder) -> Result<Self> {
+        HandlerBuilder::from_env(env, endpoint).map(HandlerBuilder::build)
+    }
+
+    /// Processes an incoming HTTP request through the Restate endpoint.
+    ///
+    /// Delegates to [`Endpoint::handle_with_options`] with the configured
+    /// [`ProtocolMode`], then converts the response body into a
+    /// Workers-compatible [`Body`].
+    ///
+    /// Requests rejected by the handler itself (see [`Error`]) are answered
+    /// with an error response. Failures to produce the response are returned
+    /// as errors, unless [`HandlerBuilder::respond_with_errors`] is enabled.
+    pub async fn handle(&self, req: Request<Body>) -> Result<Response<Body>> {
+        self.respond(self.serve(req, None).await)
+    }
+
+    /// Processes an incoming HTTP request through the Restate endpoint,
+    /// exposing the worker bindings to the invoked Restate handlers.
+    ///
+    /// Works like [`Handler::handle`], but handlers can access `env` and `ctx`
+    /// through [`WorkerContextExt`](crate::WorkerContextExt) while the
+    /// invocation runs.
+    pub async fn handle_with_env(
+        &self,
+        req: Request<Body>,
+        env: Env,
+        ctx: worker::Context,
+    ) -> Result<Response<Body>> {
+        let bindings = Arc::new(Bindings::new(env, ctx));
+
+        self.respond(self.serve(req, Some(bindings)).await)
+    }
+
+    pub(crate) fn respond(&self, response: Response<ResponseBody>) -> Result<Response<Body>> {
+        into_worker_response(response).or_else(|e| {
+            let error = Error::Body(e);
+            console_error!("{error}");
+
+            let Some(response) = self.error_response(&error) else {
+                return Err(error.into());
+            };
+
+            // Should the body conversion keep failing, at least preserve the status code.
+            into_worker_response(response)
+                .or_else(|_| Ok(error.to_response().map(|_| Body::empty())))
+        })
+    }
+
+    /// Returns the response to a failure to produce the response, if
+    /// [`HandlerBuilder::respond_with_errors`] is enabled.
+    fn error_response(&self, error: &Error) -> Option<Response<Full<Bytes>>> {
+        self.inner.respond_with_errors.then(|| error.to_response())
+    }
+
+    /// Applies the configured options to the request and forwards it to the
+    /// Restate endpoint, making `bindings` current while the invocation runs.
+    pub(crate) async fn serve<B>(
+        &self,
+        req: Request<B>,
+        bindings: Option<Arc<Bindings>>,
+    ) -> Response<ResponseBody>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+    {
+        // The body is split off, as it may not be Sync.
+        let (parts, body) = req.into_parts();
+        let response = match self.admit(&parts).await {
+            Ok(()) => {
+                self.invoke(Request::from_parts(parts, body), bindings)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        let mut response = response.unwrap_or_else(|e| e.to_response().map(Either::Left));
+
+        for (name, value) in &self.inner.response_headers {
+            response.headers_mut().insert(name, value.clone());
+        }
+
+        response
+    }
+
+    /// Rejects requests from clients that are not allowed, or that are not
+    /// authenticated, before they reach the endpoint.
+    async fn admit(&self, parts: &Parts) -> std::result::Result<(), Error> {
+        if let Some(allowlist) = &self.inner.ip_allowlist {
+            allowlist.check(&parts.headers)?;
+        }
+
+        if let Some(access) = &self.inner.access {
+            access.validate(&parts.headers).await?;
+        }
+
+        if let Some(authenticator) = &self.inner.authenticator {
+            authenticator.authenticate(parts).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn invoke<B>(
+        &self,
+        req: Request<B>,
+        bindings: Option<Arc<Bindings>>,
+    ) -> std::result::Result<Response<ResponseBody>, Error>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+    {
+        if self.inner.catch_panics {
+            panic::catch_unwind(async {
+                let response = self.forward(req)?;
+                buffer(response.map(|body| Scoped::new(body, bindings))).await
+            })
+            .await
+            .unwrap_or_else(|message| Err(Error::Panic(message)))
+            .map(|response| response.map(Either::Left))
+        } else {
+            self.forward(req)
+                .map(|response| response.map(|body| Either::Right(Scoped::new(body, bindings))))
+        }
+    }
+
+    fn forward<B>(
+        &self,
+        req: Request<B>,
+    ) -> std::result::Result<Response<endpoint::ResponseBody>, Error>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+    {
+        let (mut parts, body) = req.into_parts();
+
+        if let Some(prefix) = &self.inner.path_prefix {
+            parts.uri = strip_path_prefix(&parts.uri, prefix)
+                .ok_or_else(|| Error::NotFound(parts.uri.path().to_owned()))?;
+        }
+
+        if let Some(current) = &self.inner.version_id {
+            parts.uri = match version::strip_version(&parts.uri, current) {
+                Pinned::Match(uri) => uri,
+                Pinned::Mismatch(requested) => {
+                    return Err(Error::VersionMismatch {
+                        requested,
+                        current: current.clone(),
+                    });
+                }
+                Pinned::Unpinned => return Err(Error::NotFound(parts.uri.path().to_owned())),
+            };
+        }
+
+        for name in &self.inner.removed_request_headers {
+            parts.headers.remove(name);
+        }
+
+        let limit = self.inner.max_request_body_size.unwrap_or(usize::MAX);
+        let declared_length = parts
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+
+        if declared_length.is_some_and(|length| length > limit as u64) {
+            return Err(Error::PayloadTooLarge(limit));
+        }
+
+        let req = Request::from_parts(parts, Limited::new(body, limit));
+        let options = HandleOptions {
+            protocol_mode: self.protocol_mode(),
+        };
+
+        Ok(self.inner.endpoint.handle_with_options(req, options))
+    }
+
+    fn protocol_mode(&self) -> ProtocolMode {
+        // ProtocolMode is neither Clone nor Copy, so a fresh value is created per request.
+        match self.inner.protocol_mode {
+            ProtocolMode::RequestResponse => ProtocolMode::RequestResponse,
+            ProtocolMode::BidiStream => ProtocolMode::BidiStream,
+        }
+    }
+}
+
+/// Builder for [`Handler`].
+///
+/// Owns the Restate [`Endpoint`] along with every option the adapter applies
+/// when forwarding requests to it.
+///
+/// ```rust,ignore
+/// use restate_sdk::prelude::{Endpoint, ProtocolMode};
+/// use restate_worker::Handler;
+///
+/// let handler = Handler::builder(endpoint)
+///     .protocol_mode(ProtocolMode::RequestResponse)
+///     .max_request_body_size(1024 * 1024)
+///     .remove_request_header(http::header::COOKIE)
+///     .build();
+/// ```
+pub struct HandlerBuilder {
+    endpoint: Endpoint,
+    protocol_mode: ProtocolMode,
+    path_prefix: Option<String>,
+    version_id: Option<String>,
+    ip_allowlist: Option<IpAllowlist>,
+    access: Option<CloudflareAccess>,
+    authenticator: Option<Authenticator>,
+    max_request_body_size: Option<usize>,
+    removed_request_headers: Vec<HeaderName>,
+    response_headers: HeaderMap,
+    respond_with_errors: bool,
+    catch_panics: bool,
+}
+
+impl HandlerBuilder {
+    /// Creates a new builder backed by the given Restate endpoint.
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self {
+            endpoint,
+            protocol_mode: ProtocolMode::RequestResponse,
+            path_prefix: None,
+            version_id: None,
+            ip_allowlist: None,
+            access: None,
+            authenticator: None,
+            max_request_body_size: None,
+            removed_request_headers: Vec::new(),
+            response_headers: HeaderMap::new(),
+            respond_with_errors: false,
+            catch_panics: false,
+        }
+    }
+
+    /// Creates a new builder with request identity verification enabled.
+    ///
+    /// Reads the Restate identity keys from the
+    /// [`IDENTITY_KEYS_BINDING`](crate::IDENTITY_KEYS_BINDING) secret or
+    /// variable and applies them to the endpoint builder before building the
+    /// endpoint.
+    ///
+    /// Fails if the binding is missing or contains no valid keys.
+    pub fn from_env(env: &Env, endpoint: endpoint::Builder) -> Result<Self> {
+        let endpoint = identity::identity_keys_from_env(env, endpoint)?;
+
+        Ok(Self::new(endpoint.build()))
+    }
+
+    /// Sets the protocol mode used to invoke the endpoint.
+    ///
+    /// Defaults to [`ProtocolMode::RequestResponse`], which is the only mode
+    /// supported by the Cloudflare Workers runtime.
+    pub fn protocol_mode(mut self, protocol_mode: ProtocolMode) -> Self {
+        self.protocol_mode = protocol_mode;
+        self
+    }
+
+    /// Mounts the endpoint under the given path prefix (e.g. `/restate`).
+    ///
+    /// The prefix is stripped from the request path before it is forwarded to
+    /// the endpoint. Requests for paths outside the prefix are rejected with
+    /// `404 Not Found`.
+    pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.path_prefix = normalize_path_prefix(&prefix.into());
+        self
+    }
+
+    /// Only serves requests for the given worker version.
+    ///
+    /// Restate requires deployments to be immutable, while redeploying a
+    /// worker replaces the code served at its URL. With a version configured,
+    /// the endpoint is served under version-pinned paths (`/v/{version_id}`,
+    /// see [`versioned_path`](crate::versioned_path)), after any
+    /// [path prefix](Self::path_prefix). Requests for other versions are
+    /// rejected with `421 Misdirected Request`, so that in-flight invocations
+    /// are never replayed against different code, and requests outside the
+    /// versioned paths with `404 Not Found`.
+    pub fn version(mut self, version_id: impl Into<String>) -> Self {
+        self.version_id = Some(version_id.into());
+        self
+    }
+
+    /// Only serves requests for the running worker version.
+    ///
+    /// Reads the version from the
+    /// [`VERSION_METADATA_BINDING`](crate::VERSION_METADATA_BINDING) binding,
+    /// which must be declared in the wrangler configuration:
+    ///
+    /// ```toml
+    /// [version_metadata]
+    /// binding = "CF_VERSION_METADATA"
+    /// ```
+    ///
+    /// See [`HandlerBuilder::version`] for details.
+    pub fn version_from_env(self, env: &Env) -> Result<Self> {
+        Ok(self.version(version::version_id_from_env(env)?))
+    }
+
+    /// Only serves requests from clients in the given IP ranges.
+    ///
+    /// The client IP is read from the `CF-Connecting-IP` header. Requests
+    /// from other clients are rejected with `403 Forbidden` before reaching
+    /// the endpoint.
+    pub fn ip_allowlist(mut self, allowlist: IpAllowlist) -> Self {
+        self.ip_allowlist = Some(allowlist);
+        self
+    }
+
+    /// Only serves requests carrying a valid Cloudflare Access token.
+    ///
+    /// Requests without a token issued by the configured team for the
+    /// audience of the Access application are rejected with
+    /// `401 Unauthorized` before reaching the endpoint. See
+    /// [`CloudflareAccess`] for details.
+    pub fn cloudflare_access(mut self, access: CloudflareAccess) -> Self {
+        self.access = Some(access);
+        self
+    }
+
+    /// Only serves requests accepted by the given [`Authenticator`] (e.g.
+    /// carrying a bearer token).
+    ///
+    /// Unauthenticated requests are rejected with `401 Unauthorized` before
+    /// reaching the endpoint.
+    pub fn authenticator(mut self, authenticator: Authenticator) -> Self {
+        self.authenticator = Some(authenticator);
+        self
+    }
+
+    /// Limits the size of request bodies forwarded to the endpoint.
+    ///
+    /// Requests declaring a larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` without reaching the endpoint.
+    pub fn max_request_body_size(mut self, limit: usize) -> Self {
+        self.max_request_body_size = Some(limit);
+        self
+    }
+
+    /// Removes a header from incoming requests before they reach the endpoint.
+    pub fn remove_request_header(mut self, name: HeaderName) -> Self {
+        self.removed_request_headers.push(name);
+        self
+    }
+
+    /// Sets a header on every response returned by the handler.
+    ///
+    /// Overrides any header of the same name set by the endpoint.
+    pub fn response_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.response_headers.insert(name, value);
+        self
+    }
+
+    /// Always responds with a well-formed HTTP response.
+    ///
+    /// By default, [`Handler::handle`] returns an error when the response
+    /// cannot be produced, which the Workers runtime turns into an opaque
+    /// `500` response. When enabled, the error is logged and converted into a
+    /// response carrying the status code and message of the [`Error`], in the
+    /// format used by the Restate SDK.
+    pub fn respond_with_errors(mut self) -> Self {
+        self.respond_with_errors = true;
+        self
+    }
+
+    /// Converts a panicking Restate handler into an error response.
+    ///
+    /// When enabled, the handler runs the invocation to completion under
+    /// [`std::panic::catch_unwind`] and responds to a panic with a `500`
+    /// response carrying the panic message (see [`Error::Panic`]), which the
+    /// Restate server records as the cause of a retryable failure. This
+    /// requires the whole response to be buffered instead of streamed.
+    ///
+    /// Catching panics requires them to unwind: with the default
+    /// `panic = "abort"` strategy of `wasm32-unknown-unknown`, the isolate is
+    /// torn down anyway. The worker must be built with `-Cpanic=unwind` on a
+    /// nightly toolchain (see the [crate documentation](crate#panics)).
+    ///
+    /// Enabling this option installs a process-wide panic hook, which records
+    /// the panic message and logs it to the console before calling the
+    /// previously installed hook.
+    pub fn catch_panics(mut self) -> Self {
+        self.catch_panics = true;
+        self
+    }
+
+    /// Builds the [`Handler`].
+    pub fn build(self) -> Handler {
+        if self.catch_panics {
+            panic::install_hook();
+        }
+
+        let inner = Inner {
+            endpoint: self.endpoint,
+            protocol_mode: self.protocol_mode,
+            path_prefix: self.path_prefix,
+            version_id: self.version_id,
+            ip_allowlist: self.ip_allowlist,
+            access: self.access,
+            authenticator: self.authenticator,
+            max_request_body_size: self.max_request_body_size,
+            removed_request_headers: self.removed_request_headers,
+            response_headers: self.response_headers,
+            respond_with_errors: self.respond_with_errors,
+            catch_panics: self.catch_panics,
+        };
+
+        Handler {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+/// Conversion into a [`Handler`].
+///
+/// Used by [`main`](crate::main) to build the handler from the value returned
+/// by the annotated function, allowing it to return whichever type is most
+/// convenient:
+///
+/// - an [`Endpoint`], served with the default options
+/// - an [`endpoint::Builder`], built with the identity keys read from the
+///   worker environment (see [`Handler::from_env`])
+/// - a [`Handler`] or [`HandlerBuilder`] for full control over the options
+/// - a [`Result`] of any of the above
+pub trait IntoHandler {
+    /// Converts the value into a [`Handler`], reading configuration from the
+    /// worker environment as needed.
+    fn into_handler(self, env: &Env) -> Result<Handler>;
+}
+
+impl IntoHandler for Handler {
+    fn into_handler(self, _env: &Env) -> Result<Handler> {
+        Ok(self)
+    }
+}
+
+impl IntoHandler for HandlerBuilder {
+    fn into_handler(self, _env: &Env) -> Result<Handler> {
+        Ok(self.build())
+    }
+}
+
+impl IntoHandler for Endpoint {
+    fn into_handler(self, _env: &Env) -> Result<Handler> {
+        Ok(Handler::new(self))
+    }
+}
+
+impl IntoHandler for endpoint::Builder {
+    fn into_handler(self, env: &Env) -> Result<Handler> {
+        Handler::from_env(env, self)
+    }
+}
+
+impl<T: IntoHandler> IntoHandler for Result<T> {
+    fn into_handler(self, env: &Env) -> Result<Handler> {
+        self?.into_handler(env)
+    }
+}
+
+/// Collects the response body, so that the invocation runs to completion.
+async fn buffer<B>(response: Response<B>) -> std::result::Result<Response<Full<Bytes>>, Error>
+where
+    B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+{
+    let (parts, body) = response.into_parts();
+    let body = body
+        .collect()
+        .await
+        .map_err(|e| Error::Invocation(e.into()))?
+        .to_bytes();
+
+    Ok(Response::from_parts(parts, Full::new(body)))
+}
+
+/// Converts the response body into a Workers-compatible [`Body`].
+pub(crate) fn into_worker_response<B>(response: Response<B>) -> Result<Response<Body>>
+where
+    B: http_body::Body<Data = Bytes, Error: std::fmt::Debug> + 'static,
+{
+    let (parts, body) = response.into_parts();
+    let body = Body::from_stream(body.into_data_stream())?;
+
+    Ok(Response::from_parts(parts, body))
+}
+
+/// Normalizes a path prefix to start with a slash and end without one.
+///
+/// Returns [`None`] for the root path, which does not need to be stripped.
+pub(crate) fn normalize_path_prefix(prefix: &str) -> Option<String> {
+    let prefix = prefix.trim_matches('/');
+
+    (!prefix.is_empty()).then(|| format!("/{prefix}"))
+}
+
+/// Strips `prefix` from the path of `uri`, preserving the query string.
+///
+/// Returns [`None`] if the path is not under the prefix. The prefix only
+/// matches whole path segments, so `/restate` does not match `/restatement`.
+pub(crate) fn strip_path_prefix(uri: &Uri, prefix: &str) -> Option<Uri> {
+    let rest = uri.path().strip_prefix(prefix)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+
+    let path = if rest.is_empty() { "/" } else { rest };
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_owned(),
+    };
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::try_from(path_and_query).ok()?);
+
+    Uri::from_parts(parts).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use http::StatusCode;
+    use http_body_util::Empty;
+    use restate_sdk::prelude::{Context, HandlerResult};
+
+    fn serve<B>(handler: &Handler, req: Request<B>) -> Response<ResponseBody>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+    {
+        futures::executor::block_on(handler.serve(req, None))
+    }
+
+    pub(crate) fn read(response: Response<ResponseBody>) -> (StatusCode, String) {
+        let status = response.status();
+        let body = futures::executor::block_on(response.into_body().collect())
+            .unwrap()
+            .to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn discover(handler: &Handler, req: http::request::Builder) -> (StatusCode, String) {
+        let req = req.uri("/discover").body(Empty::<Bytes>::new()).unwrap();
+
+        read(serve(handler, req))
+    }
+
+    #[test]
+    fn handler_from_endpoint() {
+        let endpoint = Endpoint::builder().build();
+        let _handler = Handler::new(endpoint);
+    }
+
+    #[test]
+    fn request_response_mode_by_default() {
+        let handler = Handler::new(Endpoint::builder().build());
+
+        let (status, body) = discover(&handler, Request::builder());
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("REQUEST_RESPONSE"), "{body}");
+    }
+
+    #[test]
+    fn configured_protocol_mode() {
+        let handler = Handler::builder(Endpoint::builder().build())
+            .protocol_mode(ProtocolMode::BidiStream)
+            .build();
+
+        let (_, body) = discover(&handler, Request::builder());
+
+        assert!(body.contains("BIDI_STREAM"), "{body}");
+    }
+
+    #[test]
+    fn strips_path_prefix() {
+        let handler = Handler::builder(Endpoint::builder().build())
+            .path_prefix("/restate/")
+            .build();
+
+        let req = Request::get("/restate/health")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        assert_eq!(serve(&handler, req).status(), StatusCode::OK);
+
+        for path in ["/health", "/restatement/health", "/other/restate/health"] {
+            let req = Request::get(path).body(Empty::<Bytes>::new()).unwrap();
+            assert_eq!(
+                serve(&handler, req).status(),
+                StatusCode::NOT_FOUND,
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn strip_path_prefix_preserves_query() {
+        let uri = Uri::from_static("https://example.com/restate/discover?foo=bar");
+
+        let stripped = strip_path_prefix(&uri, "/restate").unwrap();
+
+        assert_eq!(stripped, "https://example.com/discover?foo=bar");
+        assert_eq!(strip_path_prefix(&uri, "/other"), None);
+    }
+
+    #[test]
+    fn rejects_oversized_body() {
+        let handler = Handler::builder(Endpoint::builder().build())
+            .max_request_body_size(16)
+            .build();
+
+        let (status, body) = discover(&handler, Request::builder().header(CONTENT_LENGTH, "17"));
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body, "request body exceeds the limit of 16 bytes");
+    }
+
+    #[test]
+    fn response_header_policy() {
+        let handler = Handler::builder(Endpoint::builder().build())
+            .response_header(
+                HeaderName::from_static("x-served-by"),
+                HeaderValue::from_static("worker"),
+            )
+            .build();
+
+        let req = Request::get("/health").body(Empty::<Bytes>::new()).unwrap();
+        let response = serve(&handler, req);
+
+        assert_eq!(response.headers()["x-served-by"], "worker");
+    }
+
+    #[test]
+    fn responds_with_errors() {
+        let error = Error::Body(worker::Error::RustError("stream closed".to_owned()));
+
+        let handler = Handler::new(Endpoint::builder().build());
+        assert!(handler.error_response(&error).is_none());
+
+        let handler = Handler::builder(Endpoint::builder().build())
+            .respond_with_errors()
+            .build();
+        let response = handler.error_response(&error).unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.headers()[http::header::CONTENT_TYPE], "text/plain");
+        assert!(response.headers().contains_key("x-restate-server"));
+
+        let body = futures::executor::block_on(response.into_body().collect())
+            .unwrap()
+            .to_bytes();
+        assert_eq!(body, "cannot convert response body: stream closed");
+    }
+
+    #[test]
+    fn requires_access_token() {
+        use crate::access::tests::{AUDIENCE, access, token};
+
+        let handler = Handler::builder(Endpoint::builder().build())
+            .cloudflare_access(access())
+            .build();
+
+        let (status, body) = discover(&handler, Request::builder());
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "unauthorized: missing Cloudflare Access token");
+
+        let (status, _) = discover(
+            &handler,
+            Request::builder().header("cf-access-jwt-assertion", token("key-1", AUDIENCE)),
+        );
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn requires_bearer_token() {
+        // The endpoint panics if the invocation reaches it.
+        let handler = Handler::builder(Endpoint::builder().bind(GreeterImpl.serve()).build())
+            .authenticator(Authenticator::bearer(["secret"]))
+            .build();
+
+        let (status, body) = read(invoke_greeter(&handler));
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "unauthorized: missing bearer token");
+
+        let (status, _) = discover(
+            &handler,
+            Request::builder().header("authorization", "Bearer secret"),
+        );
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn rejects_clients_outside_allowlist() {
+        let handler = Handler::builder(Endpoint::builder().build())
+            .ip_allowlist(IpAllowlist::parse("192.0.2.0/24").unwrap())
+            .build();
+
+        let (status, _) = discover(
+            &handler,
+            Request::builder().header("cf-connecting-ip", "192.0.2.1"),
+        );
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = discover(
+            &handler,
+            Request::builder().header("cf-connecting-ip", "203.0.113.1"),
+        );
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body, "forbidden: client IP 203.0.113.1 is not allowed");
+    }
+
+    #[test]
+    fn serves_pinned_version() {
+        let handler = Handler::builder(Endpoint::builder().build())
+            .path_prefix("/restate")
+            .version("abc")
+            .build();
+
+        let status = |path| {
+            let req = Request::get(path).body(Empty::<Bytes>::new()).unwrap();
+            serve(&handler, req).status()
+        };
+
+        assert_eq!(status("/restate/v/abc/discover"), StatusCode::OK);
+        assert_eq!(
+            status("/restate/v/def/discover"),
+            StatusCode::MISDIRECTED_REQUEST
+        );
+        assert_eq!(status("/restate/discover"), StatusCode::NOT_FOUND);
+    }
+
+    #[restate_sdk::service]
+    trait Greeter {
+        async fn greet() -> HandlerResult<String>;
+    }
+
+    struct GreeterImpl;
+
+    impl Greeter for GreeterImpl {
+        async fn greet(&self, _ctx: Context<'_>) -> HandlerResult<String> {
+            panic!("boom")
+        }
+    }
+
+    /// Builds an invocation of the handler at `path` with a minimal journal:
+    /// a `StartMessage` followed by an empty `InputCommandMessage`.
+    pub(crate) fn invocation(path: &str) -> Request<Full<Bytes>> {
+        let body: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, // StartMessage header
+            0x0a, 0x01, b'1', 0x12, 0x01, b'1', 0x18, 0x01, // id, debug_id, known_entries
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // InputCommandMessage header
+            0x72, 0x00, // empty value
+        ];
+        Request::post(path)
+            .header("content-type", "application/vnd.restate.invocation.v5")
+            .body(Full::new(Bytes::from_static(body)))
+            .unwrap()
+    }
+
+    fn invoke_greeter(handler: &Handler) -> Response<ResponseBody> {
+        serve(handler, invocation("/invoke/Greeter/greet"))
+    }
+
+    #[test]
+    fn panic_becomes_error_response() {
+        let handler = Handler::builder(Endpoint::builder().bind(GreeterImpl.serve()).build())
+            .catch_panics()
+            .build();
+
+        let (status, body) = read(invoke_greeter(&handler));
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body.starts_with("invocation panicked at"), "{body}");
+        assert!(body.contains("src/handler.rs"), "{body}");
+        assert!(body.contains("boom"), "{body}");
+    }
+
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn propagates_panics_by_default() {
+        let handler = Handler::new(Endpoint::builder().bind(GreeterImpl.serve()).build());
+
+        let _ = read(invoke_greeter(&handler));
+    }
+}
@@ -0,0 +1,66 @@
+use restate_sdk::endpoint::Builder;
+use worker::{Env, Error, Result};
+
+/// Name of the secret or variable holding the Restate request identity keys.
+///
+/// The value is a comma-separated list of `publickeyv1_...` keys. Multiple
+/// keys can be configured at the same time to support key rotation.
+pub const IDENTITY_KEYS_BINDING: &str = "RESTATE_IDENTITY_KEYS";
+
+/// Reads the identity keys from [`IDENTITY_KEYS_BINDING`] and applies them to
+/// the endpoint builder.
+///
+/// Fails if the binding is missing or does not contain any valid key, so that
+/// request signature verification cannot be disabled by accident.
+pub(crate) fn identity_keys_from_env(env: &Env, builder: Builder) -> Result<Builder> {
+    let keys = env.var(IDENTITY_KEYS_BINDING).map_err(|e| {
+        Error::RustError(format!(
+            "cannot read Restate identity keys from '{IDENTITY_KEYS_BINDING}': {e}"
+        ))
+    })?;
+
+    identity_keys(builder, &keys.to_string())
+}
+
+/// Applies a comma-separated list of identity keys to the endpoint builder.
+pub(crate) fn identity_keys(mut builder: Builder, keys: &str) -> Result<Builder> {
+    let mut count = 0;
+
+    for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+        builder = builder
+            .identity_key(key)
+            .map_err(|e| Error::RustError(format!("invalid Restate identity key: {e}")))?;
+        count += 1;
+    }
+
+    if count == 0 {
+        return Err(Error::RustError(format!(
+            "no Restate identity keys configured in '{IDENTITY_KEYS_BINDING}'"
+        )));
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restate_sdk::prelude::Endpoint;
+
+    const KEY: &str = "publickeyv1_ChjENKeMvCtRnqG2mrBK1HmPKufgFUc98K8B3ononQvp";
+    const ROTATED_KEY: &str = "publickeyv1_2G8dCQhArfvGpzPw5Vx2ALciR4xCLHfS5YaT93XjNxX9";
+
+    #[test]
+    fn applies_multiple_keys() {
+        let keys = format!("{KEY}, {ROTATED_KEY},");
+
+        assert!(identity_keys(Endpoint::builder(), &keys).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(identity_keys(Endpoint::builder(), "").is_err());
+        assert!(identity_keys(Endpoint::builder(), " , ").is_err());
+        assert!(identity_keys(Endpoint::builder(), "not-a-key").is_err());
+    }
+}
@@ -0,0 +1,680 @@
+//! Client for the [Restate ingress](https://docs.restate.dev/invoke/http),
+//! invoking Restate services from worker code.
+//!
+//! ```rust,ignore
+//! use restate_worker::ingress::IngressClient;
+//!
+//! let client = IngressClient::new("https://my-env.env.us.restate.cloud:8080")
+//!     .auth_token(api_key)?;
+//!
+//! // Request-response call
+//! let greeting: String = client.service("Greeter").handler("greet").call(&"Alice").await?;
+//!
+//! // One-way call, deduplicated by idempotency key
+//! client
+//!     .object("Cart", user_id)
+//!     .handler("checkout")
+//!     .idempotency_key(order_id)
+//!     .send(&order)
+//!     .await?;
+//!
+//! // Workflows
+//! let signup = client.workflow("Signup", user_id);
+//! signup.run().send(&user).await?;
+//! let outcome: Outcome = signup.attach().await?;
+//! ```
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use worker::Env;
+
+mod mock;
+mod transport;
+
+pub use mock::MockIngress;
+pub use transport::{FetchTransport, IngressTransport, Transport};
+
+/// Name of the service binding to the worker fronting the Restate ingress.
+pub const INGRESS_BINDING: &str = "RESTATE_INGRESS";
+
+/// Name of the variable holding the URL of the Restate ingress.
+pub const INGRESS_URL_BINDING: &str = "RESTATE_INGRESS_URL";
+
+/// Name of the secret holding the token authenticating with the Restate
+/// ingress.
+///
+/// Same secret as [`ADMIN_TOKEN_BINDING`](crate::ADMIN_TOKEN_BINDING), as
+/// Restate Cloud API keys authenticate with both.
+pub const INGRESS_TOKEN_BINDING: &str = crate::ADMIN_TOKEN_BINDING;
+
+/// Base URL of requests sent through the service binding when no ingress URL
+/// is configured. Only the path of these requests matters.
+const SERVICE_BINDING_URL: &str = "https://restate-ingress";
+
+const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Status code returned by the ingress when the output of an invocation is
+/// not available yet.
+const NOT_READY: u16 = 470;
+
+/// Errors returned by an [`IngressClient`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum IngressError {
+    /// The request could not be sent to the ingress.
+    #[error("cannot reach the Restate ingress: {0}")]
+    Transport(#[source] worker::Error),
+
+    /// The ingress (or the invoked handler) responded with an error.
+    #[error("Restate ingress responded with status {status}: {message}")]
+    Status {
+        /// Status code of the response.
+        status: StatusCode,
+        /// Error message of the response.
+        message: String,
+    },
+
+    /// The request could not be built (e.g. an idempotency key that is not a
+    /// valid header value).
+    #[error("invalid ingress request: {0}")]
+    InvalidRequest(String),
+
+    /// The request or response payload could not be (de)serialized.
+    #[error("invalid payload: {0}")]
+    Payload(#[from] serde_json::Error),
+}
+
+impl IngressError {
+    /// Returns the status code of the ingress response, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            IngressError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl From<IngressError> for worker::Error {
+    fn from(e: IngressError) -> Self {
+        match e {
+            IngressError::Transport(e) => e,
+            e => worker::Error::RustError(e.to_string()),
+        }
+    }
+}
+
+type Result<T, E = IngressError> = std::result::Result<T, E>;
+
+/// Client for the Restate ingress.
+///
+/// Requests are sent with the Workers [`Fetch`](worker::Fetch) API by
+/// default. Use [`IngressClient::with_transport`] to send them differently
+/// (e.g. to a [`MockIngress`] in tests).
+#[derive(Debug, Clone)]
+pub struct IngressClient<T = FetchTransport> {
+    base_url: String,
+    headers: HeaderMap,
+    transport: T,
+}
+
+impl IngressClient {
+    /// Creates a client for the ingress at `base_url`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_transport(base_url, FetchTransport)
+    }
+}
+
+impl IngressClient<IngressTransport> {
+    /// Creates a client configured from the worker environment.
+    ///
+    /// Requests are sent through the [`INGRESS_BINDING`] service binding if it
+    /// is configured, avoiding egress to the public internet, or to the URL in
+    /// [`INGRESS_URL_BINDING`] otherwise. If set, the
+    /// [`INGRESS_TOKEN_BINDING`] secret authenticates the requests.
+    pub fn from_env(env: &Env) -> worker::Result<Self> {
+        let binding = env.service(INGRESS_BINDING).ok();
+        let url = env.var(INGRESS_URL_BINDING).ok().map(|url| url.to_string());
+        let token = env
+            .secret(INGRESS_TOKEN_BINDING)
+            .ok()
+            .map(|token| token.to_string());
+
+        Self::configured(binding, url, token.as_deref())
+    }
+}
+
+impl<F: Transport> IngressClient<IngressTransport<F>> {
+    fn configured(
+        binding: Option<F>,
+        url: Option<String>,
+        token: Option<&str>,
+    ) -> worker::Result<Self> {
+        let client = match (binding, url) {
+            (Some(fetcher), url) => Self::with_transport(
+                url.unwrap_or_else(|| SERVICE_BINDING_URL.to_owned()),
+                IngressTransport::ServiceBinding(fetcher),
+            ),
+            (None, Some(url)) => Self::with_transport(url, IngressTransport::Fetch(FetchTransport)),
+            (None, None) => {
+                return Err(worker::Error::RustError(format!(
+                    "Restate ingress is not configured: set the '{INGRESS_BINDING}' service binding or the '{INGRESS_URL_BINDING}' variable"
+                )));
+            }
+        };
+
+        match token {
+            Some(token) => client
+                .auth_token(token)
+                .map_err(|e| worker::Error::RustError(format!("invalid Restate auth token: {e}"))),
+            None => Ok(client),
+        }
+    }
+}
+
+impl<T: Transport> IngressClient<T> {
+    /// Creates a client for the ingress at `base_url`, sending requests with
+    /// the given transport.
+    pub fn with_transport(base_url: impl Into<String>, transport: T) -> Self {
+        let base_url = base_url.into();
+
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            headers: HeaderMap::new(),
+            transport,
+        }
+    }
+
+    /// Sets a header on every request sent to the ingress.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Authenticates requests with the given bearer token (e.g. a Restate
+    /// Cloud API key).
+    pub fn auth_token(self, token: &str) -> Result<Self, http::header::InvalidHeaderValue> {
+        let mut value = HeaderValue::try_from(format!("Bearer {token}"))?;
+        value.set_sensitive(true);
+
+        Ok(self.header(AUTHORIZATION, value))
+    }
+
+    /// Targets a handler of a service.
+    pub fn service(&self, service: &str) -> Target<'_, T> {
+        Target::new(self, format!("/{service}"))
+    }
+
+    /// Targets a handler of a virtual object.
+    pub fn object(&self, object: &str, key: &str) -> Target<'_, T> {
+        Target::new(self, format!("/{object}/{}", encode(key)))
+    }
+
+    /// Targets a workflow.
+    pub fn workflow(&self, workflow: &str, id: &str) -> Workflow<'_, T> {
+        Workflow {
+            client: self,
+            name: workflow.to_owned(),
+            id: encode(id),
+        }
+    }
+
+    /// Completes the awakeable with the given id with a value.
+    pub async fn resolve_awakeable<V: Serialize>(&self, id: &str, value: &V) -> Result<()> {
+        let path = format!("/restate/awakeables/{}/resolve", encode(id));
+        self.send(Method::POST, &path, HeaderMap::new(), json(value)?)
+            .await
+            .map(drop)
+    }
+
+    /// Completes the awakeable with the given id with a failure.
+    pub async fn reject_awakeable(&self, id: &str, reason: &str) -> Result<()> {
+        let path = format!("/restate/awakeables/{}/reject", encode(id));
+        let body = Bytes::copy_from_slice(reason.as_bytes());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+        self.send(Method::POST, &path, headers, Some(body))
+            .await
+            .map(drop)
+    }
+
+    /// Sends a request to the ingress, failing on unsuccessful responses.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        mut headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<http::Response<Bytes>> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("{}{path}", self.base_url))
+            .body(body.clone().unwrap_or_default())
+            .map_err(|e| IngressError::InvalidRequest(e.to_string()))?;
+
+        if body.is_some() && !headers.contains_key(CONTENT_TYPE) {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        for (name, value) in self.headers.iter().chain(&headers) {
+            request.headers_mut().insert(name, value.clone());
+        }
+
+        let response = self
+            .transport
+            .send(request)
+            .await
+            .map_err(IngressError::Transport)?;
+
+        if !response.status().is_success() {
+            return Err(IngressError::Status {
+                status: response.status(),
+                message: error_message(response.body()),
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+/// Handler of a service, virtual object or workflow to invoke.
+pub struct Target<'a, T> {
+    client: &'a IngressClient<T>,
+    path: String,
+}
+
+impl<'a, T: Transport> Target<'a, T> {
+    fn new(client: &'a IngressClient<T>, path: String) -> Self {
+        Self { client, path }
+    }
+
+    /// Selects the handler to invoke.
+    pub fn handler(self, handler: &str) -> Invocation<'a, T> {
+        Invocation {
+            client: self.client,
+            path: format!("{}/{handler}", self.path),
+            idempotency_key: None,
+        }
+    }
+}
+
+/// Invocation of a handler, sent with [`call`](Invocation::call) or
+/// [`send`](Invocation::send).
+///
+/// Inputs and outputs are encoded as JSON. A `null` input (e.g. `&()`) is
+/// sent as an empty body, as expected by handlers without input.
+pub struct Invocation<'a, T> {
+    client: &'a IngressClient<T>,
+    path: String,
+    idempotency_key: Option<String>,
+}
+
+impl<T: Transport> Invocation<'_, T> {
+    /// Sets the idempotency key of the invocation.
+    ///
+    /// Restate executes invocations with the same idempotency key only once,
+    /// returning the result of the first one to later requests.
+    ///
+    /// Keys that are not valid header values (e.g. containing control
+    /// characters) fail the invocation with [`IngressError::InvalidRequest`].
+    pub fn idempotency_key(mut self, key: &str) -> Self {
+        self.idempotency_key = Some(key.to_owned());
+        self
+    }
+
+    fn headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+
+        if let Some(key) = &self.idempotency_key {
+            let value = HeaderValue::try_from(key).map_err(|e| {
+                IngressError::InvalidRequest(format!("invalid idempotency key: {e}"))
+            })?;
+            headers.insert(IDEMPOTENCY_KEY, value);
+        }
+
+        Ok(headers)
+    }
+
+    /// Invokes the handler and waits for its output.
+    pub async fn call<I: Serialize, O: DeserializeOwned>(self, input: &I) -> Result<O> {
+        let response = self
+            .client
+            .send(Method::POST, &self.path, self.headers()?, json(input)?)
+            .await?;
+
+        output(response.body())
+    }
+
+    /// Invokes the handler without waiting for its output.
+    pub async fn send<I: Serialize>(self, input: &I) -> Result<SendResponse> {
+        self.send_with_path(format!("{}/send", self.path), input)
+            .await
+    }
+
+    /// Invokes the handler after the given delay, without waiting for its
+    /// output.
+    pub async fn send_after<I: Serialize>(
+        self,
+        input: &I,
+        delay: Duration,
+    ) -> Result<SendResponse> {
+        let path = format!("{}/send?delay={}ms", self.path, delay.as_millis());
+
+        self.send_with_path(path, input).await
+    }
+
+    async fn send_with_path<I: Serialize>(&self, path: String, input: &I) -> Result<SendResponse> {
+        let response = self
+            .client
+            .send(Method::POST, &path, self.headers()?, json(input)?)
+            .await?;
+
+        Ok(serde_json::from_slice(response.body())?)
+    }
+}
+
+/// Response of the ingress to a one-way invocation.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendResponse {
+    invocation_id: String,
+    status: SendStatus,
+}
+
+impl SendResponse {
+    /// Returns the identifier of the invocation.
+    pub fn invocation_id(&self) -> &str {
+        &self.invocation_id
+    }
+
+    /// Returns whether the invocation was accepted by this request, or by an
+    /// earlier one with the same idempotency key.
+    pub fn status(&self) -> SendStatus {
+        self.status
+    }
+}
+
+/// Outcome of a one-way invocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub enum SendStatus {
+    /// The invocation was accepted.
+    Accepted,
+    /// An invocation with the same idempotency key was accepted before.
+    PreviouslyAccepted,
+}
+
+/// Workflow instance, identified by its name and id.
+pub struct Workflow<'a, T> {
+    client: &'a IngressClient<T>,
+    name: String,
+    id: String,
+}
+
+impl<'a, T: Transport> Workflow<'a, T> {
+    /// Targets the `run` handler, starting the workflow.
+    ///
+    /// A workflow runs only once per id: invoking `run` again attaches to the
+    /// existing run.
+    pub fn run(&self) -> Invocation<'a, T> {
+        self.handler("run")
+    }
+
+    /// Targets a shared handler of the workflow (e.g. to signal it).
+    pub fn handler(&self, handler: &str) -> Invocation<'a, T> {
+        Target::new(self.client, format!("/{}/{}", self.name, self.id)).handler(handler)
+    }
+
+    /// Waits for the workflow to complete and returns its output.
+    pub async fn attach<O: DeserializeOwned>(&self) -> Result<O> {
+        let response = self
+            .client
+            .send(Method::GET, &self.path("attach"), HeaderMap::new(), None)
+            .await?;
+
+        output(response.body())
+    }
+
+    /// Returns the output of the workflow, or [`None`] if it has not completed
+    /// yet.
+    pub async fn output<O: DeserializeOwned>(&self) -> Result<Option<O>> {
+        let result = self
+            .client
+            .send(Method::GET, &self.path("output"), HeaderMap::new(), None)
+            .await;
+
+        match result {
+            Ok(response) => output(response.body()).map(Some),
+            Err(e)
+                if e.status()
+                    .is_some_and(|status| status.as_u16() == NOT_READY) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn path(&self, action: &str) -> String {
+        format!("/restate/workflow/{}/{}/{action}", self.name, self.id)
+    }
+}
+
+/// Encodes an input as JSON, mapping `null` to an empty body.
+fn json<I: Serialize>(input: &I) -> Result<Option<Bytes>> {
+    let body = serde_json::to_vec(input)?;
+
+    Ok((body != b"null").then(|| Bytes::from(body)))
+}
+
+/// Decodes an output from JSON, treating an empty body as `null`.
+fn output<O: DeserializeOwned>(body: &Bytes) -> Result<O> {
+    let body: &[u8] = if body.is_empty() { b"null" } else { body };
+
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Extracts the error message from an ingress error response.
+fn error_message(body: &Bytes) -> String {
+    #[derive(serde::Deserialize)]
+    struct ErrorBody {
+        message: String,
+    }
+
+    serde_json::from_slice::<ErrorBody>(body)
+        .map(|error| error.message)
+        .unwrap_or_else(|_| String::from_utf8_lossy(body).into_owned())
+}
+
+/// Percent-encodes a key for use as a path segment.
+fn encode(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use http::Response;
+
+    fn client<F>(ingress: &MockIngress<F>) -> IngressClient<&MockIngress<F>>
+    where
+        F: Fn(&Request<Bytes>) -> Response<Bytes>,
+    {
+        IngressClient::with_transport("http://restate:8080/", ingress)
+            .auth_token("secret")
+            .unwrap()
+    }
+
+    #[test]
+    fn calls_handler() {
+        let ingress = MockIngress::new(|_| MockIngress::json(&"Hello, Alice!"));
+        let client = client(&ingress);
+
+        let greeting: String =
+            block_on(client.service("Greeter").handler("greet").call(&"Alice")).unwrap();
+
+        assert_eq!(greeting, "Hello, Alice!");
+
+        let request = &ingress.requests()[0];
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.uri(), "http://restate:8080/Greeter/greet");
+        assert_eq!(request.headers()[AUTHORIZATION], "Bearer secret");
+        assert_eq!(request.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(request.body(), r#""Alice""#);
+    }
+
+    #[test]
+    fn sends_with_idempotency_key_and_delay() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let client = client(&ingress);
+
+        let response = block_on(
+            client
+                .object("Cart", "user/1")
+                .handler("checkout")
+                .idempotency_key("order-1")
+                .send_after(&(), Duration::from_secs(90)),
+        )
+        .unwrap();
+
+        assert_eq!(response.invocation_id(), "inv_1");
+        assert_eq!(response.status(), SendStatus::Accepted);
+
+        let request = &ingress.requests()[0];
+        assert_eq!(
+            request.uri(),
+            "http://restate:8080/Cart/user%2F1/checkout/send?delay=90000ms"
+        );
+        assert_eq!(request.headers()[IDEMPOTENCY_KEY], "order-1");
+        assert!(request.body().is_empty());
+        assert!(!request.headers().contains_key(CONTENT_TYPE));
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let client = client(&ingress);
+
+        let error = block_on(
+            client
+                .service("Greeter")
+                .handler("greet")
+                .idempotency_key("order\n1")
+                .send(&()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, IngressError::InvalidRequest(_)), "{error}");
+
+        let client = IngressClient::with_transport("http://restate 8080", &ingress);
+        let error = block_on(client.service("Greeter").handler("greet").send(&())).unwrap_err();
+        assert!(matches!(error, IngressError::InvalidRequest(_)), "{error}");
+
+        assert!(ingress.requests().is_empty());
+    }
+
+    #[test]
+    fn workflow_lifecycle() {
+        let ingress = MockIngress::new(|req| match req.uri().path() {
+            "/Signup/alice/run/send" => MockIngress::accepted(req),
+            "/restate/workflow/Signup/alice/output" => {
+                MockIngress::status(StatusCode::from_u16(NOT_READY).unwrap(), "not ready")
+            }
+            "/restate/workflow/Signup/alice/attach" => MockIngress::json(&true),
+            path => panic!("unexpected request to {path}"),
+        });
+        let client = client(&ingress);
+        let signup = client.workflow("Signup", "alice");
+
+        block_on(signup.run().send(&"alice@example.com")).unwrap();
+        assert_eq!(block_on(signup.output::<bool>()).unwrap(), None);
+        assert!(block_on(signup.attach::<bool>()).unwrap());
+    }
+
+    #[test]
+    fn resolves_awakeables() {
+        let ingress = MockIngress::new(|_| MockIngress::status(StatusCode::ACCEPTED, ""));
+        let client = client(&ingress);
+
+        block_on(client.resolve_awakeable("sign_1", &42)).unwrap();
+        block_on(client.reject_awakeable("sign_1", "declined")).unwrap();
+
+        let requests = ingress.requests();
+        assert_eq!(
+            requests[0].uri().path(),
+            "/restate/awakeables/sign_1/resolve"
+        );
+        assert_eq!(requests[0].body(), "42");
+        assert_eq!(
+            requests[1].uri().path(),
+            "/restate/awakeables/sign_1/reject"
+        );
+        assert_eq!(requests[1].headers()[CONTENT_TYPE], "text/plain");
+    }
+
+    #[test]
+    fn prefers_service_binding() {
+        let fetcher = MockIngress::new(|_| MockIngress::json(&"Hello, Alice!"));
+
+        let client = IngressClient::configured(Some(&fetcher), None, Some("secret")).unwrap();
+        let _: String =
+            block_on(client.service("Greeter").handler("greet").call(&"Alice")).unwrap();
+
+        let request = &fetcher.requests()[0];
+        assert_eq!(request.uri(), "https://restate-ingress/Greeter/greet");
+        assert_eq!(request.headers()[AUTHORIZATION], "Bearer secret");
+
+        let client = IngressClient::<IngressTransport<FetchTransport>>::configured(
+            None,
+            Some("https://restate.example.com".to_owned()),
+            None,
+        )
+        .unwrap();
+        assert!(matches!(client.transport, IngressTransport::Fetch(_)));
+
+        assert!(
+            IngressClient::<IngressTransport<FetchTransport>>::configured(None, None, None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let ingress = MockIngress::new(|_| {
+            MockIngress::status(
+                StatusCode::NOT_FOUND,
+                r#"{"message": "service 'Greeter' not found"}"#,
+            )
+        });
+        let client = client(&ingress);
+
+        let error = block_on(
+            client
+                .service("Greeter")
+                .handler("greet")
+                .call::<_, String>(&()),
+        )
+        .unwrap_err();
+
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+        assert!(
+            error.to_string().ends_with("service 'Greeter' not found"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+
+use bytes::Bytes;
+use http::{Request, Response, StatusCode};
+use worker::Result;
+
+use super::Transport;
+
+/// In-memory stand-in for the Restate ingress.
+///
+/// Records every request and answers it with the given responder, so that
+/// code using an [`IngressClient`](super::IngressClient) can be tested
+/// without a Restate server.
+///
+/// ```rust,ignore
+/// use restate_worker::ingress::{IngressClient, MockIngress};
+///
+/// let ingress = MockIngress::new(|_req| MockIngress::json(&"Hello, Alice!"));
+/// let client = IngressClient::with_transport("http://restate", &ingress);
+///
+/// let greeting: String = client.service("Greeter").handler("greet").call(&"Alice").await?;
+///
+/// assert_eq!(ingress.requests()[0].uri().path(), "/Greeter/greet");
+/// ```
+pub struct MockIngress<F> {
+    responder: F,
+    requests: RefCell<Vec<Request<Bytes>>>,
+}
+
+impl<F> MockIngress<F>
+where
+    F: Fn(&Request<Bytes>) -> Response<Bytes>,
+{
+    /// Creates a mock ingress answering requests with `responder`.
+    pub fn new(responder: F) -> Self {
+        Self {
+            responder,
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<Request<Bytes>> {
+        self.requests.borrow().clone()
+    }
+}
+
+impl MockIngress<fn(&Request<Bytes>) -> Response<Bytes>> {
+    /// Returns a `200 OK` response with the JSON representation of `value`.
+    pub fn json<T: serde::Serialize>(value: &T) -> Response<Bytes> {
+        let body = serde_json::to_vec(value).expect("value must be serializable");
+
+        Self::status(StatusCode::OK, body)
+    }
+
+    /// Returns the response of the ingress to a send request whose
+    /// invocation was accepted.
+    ///
+    /// Can be used as the responder of a mock ingress only receiving send
+    /// requests, e.g. `MockIngress::new(MockIngress::accepted)`.
+    pub fn accepted(_request: &Request<Bytes>) -> Response<Bytes> {
+        Self::json(&serde_json::json!({
+            "invocationId": "inv_1",
+            "status": "Accepted",
+        }))
+    }
+
+    /// Returns a response with the given status and body.
+    pub fn status(status: StatusCode, body: impl Into<Bytes>) -> Response<Bytes> {
+        let mut response = Response::new(body.into());
+        *response.status_mut() = status;
+        response
+    }
+}
+
+impl<F> Transport for MockIngress<F>
+where
+    F: Fn(&Request<Bytes>) -> Response<Bytes>,
+{
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>> {
+        let response = (self.responder)(&request);
+        self.requests.borrow_mut().push(request);
+
+        Ok(response)
+    }
+}
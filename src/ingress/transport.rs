@@ -0,0 +1,66 @@
+use bytes::Bytes;
+use http::{Request, Response};
+use http_body_util::{BodyExt, Full};
+use worker::{Fetcher, Result};
+
+use crate::fetch;
+
+/// Sends requests to the Restate ingress on behalf of an
+/// [`IngressClient`](super::IngressClient).
+pub trait Transport {
+    /// Sends the request and buffers the response.
+    fn send(&self, request: Request<Bytes>) -> impl Future<Output = Result<Response<Bytes>>>;
+}
+
+impl<T: Transport> Transport for &T {
+    fn send(&self, request: Request<Bytes>) -> impl Future<Output = Result<Response<Bytes>>> {
+        (**self).send(request)
+    }
+}
+
+/// Sends requests to the ingress over the public internet with the Workers
+/// [`Fetch`](worker::Fetch) API.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchTransport;
+
+impl Transport for FetchTransport {
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>> {
+        let (parts, body) = request.into_parts();
+        let response =
+            fetch::fetch(parts.method, &parts.uri.to_string(), &parts.headers, &body).await?;
+
+        Ok(response.into())
+    }
+}
+
+/// Sends requests to an ingress fronted by another worker through a
+/// [service binding](https://developers.cloudflare.com/workers/runtime-apis/bindings/service-bindings/),
+/// without leaving the Cloudflare network.
+impl Transport for Fetcher {
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>> {
+        let response = self.fetch_request(request.map(Full::new)).await?;
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await?.to_bytes();
+
+        Ok(Response::from_parts(parts, body))
+    }
+}
+
+/// Transport selected from the worker configuration by
+/// [`IngressClient::from_env`](super::IngressClient::from_env).
+#[derive(Debug, Clone)]
+pub enum IngressTransport<F = Fetcher> {
+    /// Requests are sent over the public internet.
+    Fetch(FetchTransport),
+    /// Requests are sent through a service binding.
+    ServiceBinding(F),
+}
+
+impl<F: Transport> Transport for IngressTransport<F> {
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>> {
+        match self {
+            IngressTransport::Fetch(transport) => transport.send(request).await,
+            IngressTransport::ServiceBinding(fetcher) => fetcher.send(request).await,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::OnceLock;
+
+use worker::{Env, Result};
+
+use crate::Handler;
+
+/// A [`Handler`] that is built once per isolate and reused across requests.
+///
+/// Building an [`Endpoint`](restate_sdk::prelude::Endpoint) registers every
+/// service and prepares the discovery manifest, which is wasteful to repeat on
+/// every request. `LazyHandler` runs the factory on the first request that
+/// reaches the isolate and caches the resulting handler for subsequent ones.
+///
+/// If the factory fails, the error is returned and the factory runs again on
+/// the next request.
+///
+/// ```rust,ignore
+/// use restate_sdk::prelude::Endpoint;
+/// use restate_worker::{Handler, LazyHandler};
+/// use worker::*;
+///
+/// static HANDLER: LazyHandler = LazyHandler::new(|env| {
+///     Handler::from_env(env, Endpoint::builder().bind(my_service.serve()))
+/// });
+///
+/// #[event(fetch)]
+/// async fn fetch(req: HttpRequest, env: Env, _ctx: Context) -> Result<http::Response<Body>> {
+///     HANDLER.get_or_init(&env)?.handle(req).await
+/// }
+/// ```
+pub struct LazyHandler {
+    handler: OnceLock<Handler>,
+    init: fn(&Env) -> Result<Handler>,
+}
+
+impl LazyHandler {
+    /// Creates a new lazy handler built by the given factory.
+    pub const fn new(init: fn(&Env) -> Result<Handler>) -> Self {
+        Self {
+            handler: OnceLock::new(),
+            init,
+        }
+    }
+
+    /// Returns the cached handler, building it from the worker environment on
+    /// first use.
+    pub fn get_or_init(&self, env: &Env) -> Result<&Handler> {
+        self.get_or_try_init(|| (self.init)(env))
+    }
+
+    /// Returns the cached handler, if it has already been built.
+    pub fn get(&self) -> Option<&Handler> {
+        self.handler.get()
+    }
+
+    fn get_or_try_init(&self, init: impl FnOnce() -> Result<Handler>) -> Result<&Handler> {
+        if let Some(handler) = self.handler.get() {
+            return Ok(handler);
+        }
+
+        // Workers isolates are single-threaded, so the factory cannot race with
+        // itself. Should it ever happen, the first handler to be stored wins.
+        let handler = init()?;
+
+        Ok(self.handler.get_or_init(|| handler))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restate_sdk::prelude::Endpoint;
+    use std::cell::Cell;
+
+    static HANDLER: LazyHandler = LazyHandler::new(|_| unreachable!("factory is injected"));
+
+    #[test]
+    fn factory_runs_once() {
+        let calls = Cell::new(0);
+        let factory = || {
+            calls.set(calls.get() + 1);
+            Ok(Handler::new(Endpoint::builder().build()))
+        };
+
+        assert!(HANDLER.get().is_none());
+
+        let first: *const Handler = HANDLER.get_or_try_init(factory).unwrap();
+        let second: *const Handler = HANDLER.get_or_try_init(factory).unwrap();
+
+        assert_eq!(calls.get(), 1);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn failed_factory_is_retried() {
+        let handler = LazyHandler::new(|_| unreachable!("factory is injected"));
+
+        let result = handler.get_or_try_init(|| Err("missing binding".into()));
+        assert!(result.is_err());
+        assert!(handler.get().is_none());
+
+        let result = handler.get_or_try_init(|| Ok(Handler::new(Endpoint::builder().build())));
+        assert!(result.is_ok());
+        assert!(handler.get().is_some());
+    }
+}
@@ -20,57 +20,162 @@
 //!     .build();
 //!
 //! let handler = Handler::new(endpoint);
-//! let response = handler.handle(request)?;
+//! let response = handler.handle(request).await?;
 //! ```
+//!
+//! To verify that requests are signed by Restate, use [`Handler::from_env`]
+//! instead, which reads the request identity keys from the
+//! [`IDENTITY_KEYS_BINDING`] secret. Workers reached by a self-hosted
+//! Restate cluster through Cloudflare Access can validate the Access token of
+//! each request with [`HandlerBuilder::cloudflare_access`], and others can
+//! require a bearer token or shared secret with [`HandlerBuilder::authenticator`].
+//! As defense in depth, [`HandlerBuilder::ip_allowlist`] only admits requests
+//! from the given IP ranges.
+//!
+//! To avoid rebuilding the endpoint on every request, use a [`LazyHandler`],
+//! which builds the handler once per isolate, or let the [`main`] attribute
+//! macro generate the whole fetch entrypoint.
+//!
+//! Restate handlers can access the worker environment and execution context
+//! of the current request through [`WorkerContextExt`] when requests are
+//! served with [`Handler::handle_with_env`] (or only the environment when the
+//! handler is mounted with [`RouterExt::restate`]).
+//!
+//! Worker code can invoke Restate services, workflows and awakeables through
+//! the Restate ingress with the [`ingress::IngressClient`], either over the
+//! public internet or through a service binding. [`CronTriggers`] maps the
+//! cron triggers of the worker to handler invocations.
+//!
+//! Outbound HTTP calls from Restate handlers can be recorded in the journal
+//! with [`JournaledFetch`], so that they are not repeated on replay.
+//!
+//! Since Restate deployments must be immutable, pin the handler to the
+//! running worker version with [`HandlerBuilder::version_from_env`], and
+//! register the [versioned path](versioned_path) of each version with Restate.
+//!
+//! The [`admin`] module contains the types of the Restate admin API used to
+//! register deployments, as done by the `restate-worker` command line tool.
+//! Alternatively, workers can register themselves from a `scheduled` event
+//! with [`SelfRegistration`].
+//!
+//! The [`webhook`] module receives third-party webhooks next to the handler,
+//! verifying their signature before completing awakeables or starting
+//! workflows through the ingress.
+//!
+//! To serve several Restate deployments from a single worker, dispatch
+//! requests by host, path or header with a [`MultiHandler`].
+//!
+//! Workers using [`worker::Router`] can mount the handler as a catch-all
+//! route with [`RouterExt::restate`].
+//!
+//! Use [`Handler::builder`] to customize how requests are forwarded to the
+//! endpoint.
+//!
+//! # Panics
+//!
+//! With [`HandlerBuilder::catch_panics`], a panicking Restate handler is
+//! answered with a `500` response carrying the panic message, which Restate
+//! records as the cause of the failure before retrying the invocation.
+//!
+//! This requires panics to unwind, while `wasm32-unknown-unknown` aborts on
+//! panic by default. Building with unwinding requires a nightly toolchain
+//! with the `rust-src` component, rebuilding the standard library with
+//! WebAssembly exception handling enabled:
+//!
+//! ```toml
+//! # .cargo/config.toml
+//! [unstable]
+//! build-std = ["std", "panic_unwind"]
+//!
+//! [target.wasm32-unknown-unknown]
+//! rustflags = ["-Cpanic=unwind", "-Ctarget-feature=+exception-handling"]
+//! ```
+//!
+//! # Features
+//!
+//! - `tower`: implements `tower::Service` for [`Handler`], so that it can be
+//!   composed with tower middleware.
+//! - `axum`: adds `Handler::into_axum`, which turns the handler into a
+//!   service that can be mounted in an axum router.
+//! - `queue`: adds `QueueConsumer`, which forwards Cloudflare Queues messages
+//!   to a Restate handler through the ingress.
 
-use http::{Request, Response};
-use http_body_util::BodyExt;
-use restate_sdk::prelude::{Endpoint, HandleOptions, ProtocolMode};
-use worker::{Body, Result};
-
-/// HTTP handler that forwards requests to a Restate [`Endpoint`].
-///
-/// Wraps a Restate endpoint and adapts it to the Cloudflare Workers runtime.
-/// Requests are processed using [`ProtocolMode::RequestResponse`] because
-/// Cloudflare Workers buffer the entire request body before passing it to the
-/// worker, making bidirectional streaming impossible.
-pub struct Handler {
-    endpoint: Endpoint,
-}
-
-impl Handler {
-    /// Creates a new handler backed by the given Restate endpoint.
-    pub fn new(endpoint: Endpoint) -> Self {
-        Self { endpoint }
-    }
-
-    /// Processes an incoming HTTP request through the Restate endpoint.
-    ///
-    /// Delegates to [`Endpoint::handle_with_options`] with
-    /// [`ProtocolMode::RequestResponse`], then converts the response body into
-    /// a Workers-compatible [`Body`].
-    pub fn handle(&self, req: Request<Body>) -> Result<Response<Body>> {
-        let response = self.endpoint.handle_with_options(
-            req,
-            HandleOptions {
-                protocol_mode: ProtocolMode::RequestResponse,
-            },
-        );
-
-        let (parts, body) = response.into_parts();
-        let body = Body::from_stream(body.into_data_stream())?;
+mod access;
+pub mod admin;
+mod allowlist;
+mod auth;
+#[cfg(feature = "axum")]
+mod axum;
+mod bindings;
+mod cron;
+mod error;
+mod fetch;
+mod handler;
+mod identity;
+pub mod ingress;
+mod lazy;
+mod multi;
+mod panic;
+#[cfg(feature = "queue")]
+mod queue;
+mod registration;
+mod router;
+#[cfg(feature = "tower")]
+mod service;
+mod version;
+pub mod webhook;
 
-        Ok(Response::from_parts(parts, body))
-    }
-}
+#[doc(hidden)]
+#[path = "private.rs"]
+pub mod __private;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub use access::{ACCESS_AUDIENCE_BINDING, ACCESS_TEAM_DOMAIN_BINDING, CloudflareAccess};
+pub use allowlist::{ALLOWED_IPS_BINDING, IpAllowlist};
+pub use auth::{Authenticator, ENDPOINT_TOKENS_BINDING};
+#[cfg(feature = "axum")]
+pub use axum::AxumService;
+pub use bindings::WorkerContextExt;
+pub use cron::{CronTrigger, CronTriggers};
+pub use error::Error;
+pub use fetch::{JournaledFetch, JournaledResponse};
+pub use handler::{Handler, HandlerBuilder, IntoHandler};
+pub use identity::IDENTITY_KEYS_BINDING;
+pub use lazy::LazyHandler;
+pub use multi::{MultiHandler, MultiHandlerBuilder};
+#[cfg(feature = "queue")]
+pub use queue::QueueConsumer;
+pub use registration::{
+    ADMIN_TOKEN_BINDING, ADMIN_URL_BINDING, DEPLOYMENT_URL_BINDING, REGISTRATIONS_KV_BINDING,
+    SelfRegistration,
+};
+pub use router::RouterExt;
+pub use version::{VERSION_METADATA_BINDING, versioned_path};
 
-    #[test]
-    fn handler_from_endpoint() {
-        let endpoint = Endpoint::builder().build();
-        let _handler = Handler::new(endpoint);
-    }
-}
+/// Generates the `fetch` entrypoint of a worker serving a Restate endpoint.
+///
+/// The annotated function builds the endpoint. It may take a `&worker::Env`
+/// argument and return anything that implements [`IntoHandler`]. The macro
+/// keeps the function as is and generates a `#[worker::event(fetch)]`
+/// entrypoint next to it that:
+///
+/// - calls the function on the first request and caches the resulting
+///   [`Handler`] for the lifetime of the isolate (see [`LazyHandler`])
+/// - applies the identity keys from [`IDENTITY_KEYS_BINDING`] when the
+///   function returns an endpoint builder
+/// - serves requests with [`Handler::handle_with_env`], so that Restate
+///   handlers can access the worker bindings through [`WorkerContextExt`]
+/// - responds with `500 Internal Server Error` (and logs the cause) if the
+///   handler cannot be built
+///
+/// ```rust,ignore
+/// use restate_sdk::prelude::*;
+///
+/// #[restate_worker::main]
+/// fn endpoint() -> restate_sdk::endpoint::Builder {
+///     Endpoint::builder().bind(MyServiceImpl.serve())
+/// }
+/// ```
+///
+/// The crate using the macro must depend on the `worker` crate with the `http`
+/// feature enabled.
+pub use restate_worker_macros::main;
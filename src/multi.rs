@@ -0,0 +1,376 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::header::HOST;
+use http::{HeaderMap, HeaderName, HeaderValue, Request, Response};
+use http_body_util::Full;
+use worker::{Body, Env, Result, console_error};
+
+use crate::Error;
+use crate::Handler;
+use crate::handler::{into_worker_response, normalize_path_prefix, strip_path_prefix};
+
+/// Dispatches requests to one of several [`Handler`]s.
+///
+/// Allows a single worker to serve several Restate deployments (e.g.
+/// different sets of services, or different versions of them), each with its
+/// own endpoint and options. Requests are matched against the routes in the
+/// order they were registered, and forwarded to the handler of the first
+/// matching route.
+///
+/// ```rust,ignore
+/// use restate_worker::{Handler, MultiHandler};
+///
+/// let handler = MultiHandler::builder()
+///     .host("orders.example.com", Handler::new(orders))
+///     .path("/billing", Handler::new(billing))
+///     .header(
+///         HeaderName::from_static("x-deployment"),
+///         HeaderValue::from_static("v2"),
+///         Handler::new(v2),
+///     )
+///     .build();
+///
+/// let response = handler.handle(request).await?;
+/// ```
+///
+/// Requests matching no route are rejected with `404 Not Found`, unless a
+/// [fallback](MultiHandlerBuilder::fallback) handler is configured. As these
+/// requests reach none of the handlers, the rejection follows the response
+/// options of the [`MultiHandlerBuilder`] instead.
+#[derive(Clone)]
+pub struct MultiHandler {
+    inner: Arc<Routes>,
+}
+
+struct Routes {
+    routes: Vec<(Route, Handler)>,
+    fallback: Option<Handler>,
+    response_headers: HeaderMap,
+    respond_with_errors: bool,
+}
+
+/// Condition a request must satisfy to be served by a handler of a
+/// [`MultiHandler`].
+enum Route {
+    Host(String),
+    Path(String),
+    Header(HeaderName, HeaderValue),
+}
+
+impl Route {
+    /// Returns [`None`] if the request does not match the route, or the URI to
+    /// forward it with if it has to be rewritten.
+    fn matches<B>(&self, req: &Request<B>) -> Option<Option<http::Uri>> {
+        match self {
+            Route::Host(host) => host_of(req)
+                .is_some_and(|actual| actual.eq_ignore_ascii_case(host))
+                .then_some(None),
+            Route::Path(prefix) => strip_path_prefix(req.uri(), prefix).map(Some),
+            Route::Header(name, value) => req
+                .headers()
+                .get_all(name)
+                .iter()
+                .any(|actual| actual == value)
+                .then_some(None),
+        }
+    }
+}
+
+/// Returns the host name of the request, without the port.
+fn host_of<B>(req: &Request<B>) -> Option<&str> {
+    if let Some(host) = req.uri().host() {
+        return Some(host);
+    }
+
+    let host = req.headers().get(HOST)?.to_str().ok()?;
+    let port = host.rfind(':').filter(|&i| !host[i..].contains(']'));
+
+    Some(port.map_or(host, |i| &host[..i]))
+}
+
+impl MultiHandler {
+    /// Returns a builder for a handler without any routes.
+    pub fn builder() -> MultiHandlerBuilder {
+        MultiHandlerBuilder::new()
+    }
+
+    /// Processes an incoming HTTP request with the handler of the first
+    /// matching route.
+    ///
+    /// See [`Handler::handle`] for details.
+    pub async fn handle(&self, req: Request<Body>) -> Result<Response<Body>> {
+        match self.route(req) {
+            Ok((handler, req)) => handler.handle(req).await,
+            Err(e) => self.reject(e),
+        }
+    }
+
+    /// Processes an incoming HTTP request with the handler of the first
+    /// matching route, exposing the worker bindings to the invoked Restate
+    /// handlers.
+    ///
+    /// See [`Handler::handle_with_env`] for details.
+    pub async fn handle_with_env(
+        &self,
+        req: Request<Body>,
+        env: Env,
+        ctx: worker::Context,
+    ) -> Result<Response<Body>> {
+        match self.route(req) {
+            Ok((handler, req)) => handler.handle_with_env(req, env, ctx).await,
+            Err(e) => self.reject(e),
+        }
+    }
+
+    /// Selects the handler of the first route matching the request.
+    ///
+    /// Path prefixes of the matching route are stripped from the request.
+    fn route<B>(&self, mut req: Request<B>) -> std::result::Result<(&Handler, Request<B>), Error> {
+        for (route, handler) in &self.inner.routes {
+            if let Some(uri) = route.matches(&req) {
+                if let Some(uri) = uri {
+                    *req.uri_mut() = uri;
+                }
+
+                return Ok((handler, req));
+            }
+        }
+
+        match &self.inner.fallback {
+            Some(handler) => Ok((handler, req)),
+            None => Err(Error::NotFound(req.uri().path().to_owned())),
+        }
+    }
+
+    fn reject(&self, error: Error) -> Result<Response<Body>> {
+        into_worker_response(self.rejection(&error)).or_else(|e| {
+            let error = Error::Body(e);
+            console_error!("{error}");
+
+            if !self.inner.respond_with_errors {
+                return Err(error.into());
+            }
+
+            // The body cannot be converted, so at least preserve the status code.
+            Ok(error.to_response().map(|_| Body::empty()))
+        })
+    }
+
+    /// Returns the response to a request matching no route, with the
+    /// configured response headers.
+    fn rejection(&self, error: &Error) -> Response<Full<Bytes>> {
+        let mut response = error.to_response();
+
+        for (name, value) in &self.inner.response_headers {
+            response.headers_mut().insert(name, value.clone());
+        }
+
+        response
+    }
+}
+
+/// Builder for [`MultiHandler`].
+#[derive(Default)]
+pub struct MultiHandlerBuilder {
+    routes: Vec<(Route, Handler)>,
+    fallback: Option<Handler>,
+    response_headers: HeaderMap,
+    respond_with_errors: bool,
+}
+
+impl MultiHandlerBuilder {
+    /// Creates a new builder without any routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves requests for the given host name with `handler`.
+    ///
+    /// The host is read from the request URI, or from the `Host` header for
+    /// relative URIs, and compared case-insensitively, ignoring the port.
+    pub fn host(mut self, host: impl Into<String>, handler: Handler) -> Self {
+        self.routes.push((Route::Host(host.into()), handler));
+        self
+    }
+
+    /// Serves requests under the given path prefix (e.g. `/billing`) with
+    /// `handler`.
+    ///
+    /// The prefix only matches whole path segments, and is stripped from the
+    /// request path before it is forwarded to the handler.
+    pub fn path(mut self, prefix: impl Into<String>, handler: Handler) -> Self {
+        // The root prefix is normalized to an empty one, which matches every path.
+        let prefix = normalize_path_prefix(&prefix.into()).unwrap_or_default();
+
+        self.routes.push((Route::Path(prefix), handler));
+        self
+    }
+
+    /// Serves requests carrying a header with the given value with `handler`.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue, handler: Handler) -> Self {
+        self.routes.push((Route::Header(name, value), handler));
+        self
+    }
+
+    /// Serves requests matching no route with `handler`.
+    pub fn fallback(mut self, handler: Handler) -> Self {
+        self.fallback = Some(handler);
+        self
+    }
+
+    /// Sets a header on the responses to requests matching no route.
+    ///
+    /// Responses of the routed handlers carry their own
+    /// [response headers](crate::HandlerBuilder::response_header) instead.
+    pub fn response_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.response_headers.insert(name, value);
+        self
+    }
+
+    /// Always responds to requests matching no route with a well-formed HTTP
+    /// response.
+    ///
+    /// See [`HandlerBuilder::respond_with_errors`](crate::HandlerBuilder::respond_with_errors)
+    /// for details.
+    pub fn respond_with_errors(mut self) -> Self {
+        self.respond_with_errors = true;
+        self
+    }
+
+    /// Builds the [`MultiHandler`].
+    pub fn build(self) -> MultiHandler {
+        MultiHandler {
+            inner: Arc::new(Routes {
+                routes: self.routes,
+                fallback: self.fallback,
+                response_headers: self.response_headers,
+                respond_with_errors: self.respond_with_errors,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+    use http_body_util::Empty;
+    use restate_sdk::prelude::Endpoint;
+
+    fn tagged(tag: &'static str) -> Handler {
+        Handler::builder(Endpoint::builder().build())
+            .response_header(
+                HeaderName::from_static("x-deployment"),
+                HeaderValue::from_static(tag),
+            )
+            .build()
+    }
+
+    fn multi() -> MultiHandler {
+        MultiHandler::builder()
+            .host("orders.example.com", tagged("orders"))
+            .path("/billing/", tagged("billing"))
+            .header(
+                HeaderName::from_static("x-restate-deployment"),
+                HeaderValue::from_static("v2"),
+                tagged("v2"),
+            )
+            .build()
+    }
+
+    /// Returns the deployment serving the request, or the error status.
+    fn dispatch(handler: &MultiHandler, req: http::request::Builder) -> String {
+        let req = req.body(Empty::<Bytes>::new()).unwrap();
+
+        let (handler, req) = match handler.route(req) {
+            Ok(routed) => routed,
+            Err(e) => return e.status_code().to_string(),
+        };
+
+        let response = futures::executor::block_on(handler.serve(req, None));
+        assert_eq!(response.status(), StatusCode::OK);
+
+        response.headers()["x-deployment"]
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn dispatches_by_host() {
+        let handler = multi();
+
+        let req = Request::get("https://Orders.Example.com:443/health");
+        assert_eq!(dispatch(&handler, req), "orders");
+
+        let req = Request::get("/health").header(HOST, "orders.example.com");
+        assert_eq!(dispatch(&handler, req), "orders");
+    }
+
+    #[test]
+    fn dispatches_by_path() {
+        let handler = multi();
+
+        let req = Request::get("https://example.com/billing/health");
+        assert_eq!(dispatch(&handler, req), "billing");
+
+        let req = Request::get("https://example.com/billingx/health");
+        assert_eq!(dispatch(&handler, req), "404 Not Found");
+    }
+
+    #[test]
+    fn dispatches_by_header() {
+        let handler = multi();
+
+        let req = Request::get("https://example.com/health").header("x-restate-deployment", "v2");
+        assert_eq!(dispatch(&handler, req), "v2");
+
+        let req = Request::get("https://example.com/health").header("x-restate-deployment", "v3");
+        assert_eq!(dispatch(&handler, req), "404 Not Found");
+    }
+
+    #[test]
+    fn falls_back() {
+        let handler = MultiHandler::builder()
+            .path("/billing", tagged("billing"))
+            .fallback(tagged("fallback"))
+            .build();
+
+        let req = Request::get("https://example.com/health");
+        assert_eq!(dispatch(&handler, req), "fallback");
+    }
+
+    #[test]
+    fn rejects_with_response_headers() {
+        let handler = MultiHandler::builder()
+            .path("/billing", tagged("billing"))
+            .response_header(
+                HeaderName::from_static("x-deployment"),
+                HeaderValue::from_static("none"),
+            )
+            .build();
+        let req = Request::get("https://example.com/health")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+
+        let error = handler.route(req).err().unwrap();
+        let response = handler.rejection(&error);
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()["x-deployment"], "none");
+        assert!(response.headers().contains_key("x-restate-server"));
+    }
+
+    #[test]
+    fn forwards_stripped_path() {
+        let handler = multi();
+        let req = Request::get("https://example.com/billing/discover")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+
+        let (_, req) = handler.route(req).unwrap();
+
+        assert_eq!(req.uri(), "https://example.com/discover");
+    }
+}
@@ -0,0 +1,90 @@
+use std::cell::RefCell;
+use std::future::{Future, poll_fn};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
+use std::sync::Once;
+use std::task::Poll;
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Installs a panic hook that records the message of the last panic, so that
+/// it can be reported once the panic is caught.
+///
+/// On `wasm32` targets, the message is also logged to the console, where the
+/// default hook has no output. The previously installed hook is still called.
+/// Subsequent invocations do nothing.
+pub(crate) fn install_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            let message = info.to_string();
+
+            #[cfg(target_arch = "wasm32")]
+            worker::console_error!("{message}");
+
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(message));
+            previous(info);
+        }));
+    });
+}
+
+/// Polls `future` to completion, catching panics raised while polling it.
+///
+/// Returns the panic message if the future panics. Catching panics requires
+/// them to unwind: when panics abort (the default on `wasm32` targets), the
+/// hook installed by [`install_hook`] can only record the message before the
+/// isolate is torn down.
+pub(crate) async fn catch_unwind<F: Future>(future: F) -> Result<F::Output, String> {
+    let mut future = pin!(future);
+
+    poll_fn(|cx| {
+        panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx).map(Ok)))
+            .unwrap_or_else(|payload| Poll::Ready(Err(message(payload))))
+    })
+    .await
+}
+
+fn message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = LAST_PANIC.with(|last| last.borrow_mut().take()) {
+        return message;
+    }
+
+    payload
+        .downcast::<String>()
+        .map(|message| *message)
+        .or_else(|payload| {
+            payload
+                .downcast::<&str>()
+                .map(|message| (*message).to_owned())
+        })
+        .map(|message| format!("panicked: {message}"))
+        .unwrap_or_else(|_| "panicked with a non-string payload".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catches_panic_message() {
+        install_hook();
+
+        let result = futures::executor::block_on(catch_unwind(async { panic!("boom") }));
+
+        let message = result.unwrap_err();
+        assert!(message.contains("boom"), "{message}");
+        assert!(message.contains("src/panic.rs"), "{message}");
+    }
+
+    #[test]
+    fn passes_through_output() {
+        let result = futures::executor::block_on(catch_unwind(async { 42 }));
+
+        assert_eq!(result, Ok(42));
+    }
+}
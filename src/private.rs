@@ -0,0 +1,26 @@
+//! Runtime support for the code generated by [`main`](crate::main).
+
+use worker::{Context, Env, HttpRequest, HttpResponse, Response, Result, console_error};
+
+use crate::LazyHandler;
+
+/// Serves a request with the lazily built handler.
+///
+/// Failures to build the handler are logged and turned into a generic
+/// `500 Internal Server Error` response, so that configuration details do not
+/// leak to the caller.
+pub async fn fetch(
+    handler: &LazyHandler,
+    req: HttpRequest,
+    env: Env,
+    ctx: Context,
+) -> Result<HttpResponse> {
+    match handler.get_or_init(&env) {
+        Ok(handler) => handler.handle_with_env(req, env, ctx).await,
+        Err(e) => {
+            console_error!("cannot build Restate handler: {e}");
+
+            Response::error("Internal Server Error", 500)?.try_into()
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use http::StatusCode;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use worker::{MessageBatch, MessageExt, QueueRetryOptionsBuilder, Result, console_error};
+
+use crate::ingress::{IngressClient, IngressError, IngressTransport, SendResponse, Transport};
+
+/// Forwards [Cloudflare Queues](https://developers.cloudflare.com/queues/)
+/// messages to a Restate handler.
+///
+/// Every message is sent to the handler through the Restate ingress, with the
+/// message id as idempotency key, so that messages delivered more than once
+/// invoke the handler only once. Messages accepted by the ingress are
+/// acknowledged, and so are messages that can never be accepted (rejected
+/// with a `4xx` status other than `408 Request Timeout` and `429 Too Many
+/// Requests`, or that cannot be sent at all), after logging the error. The
+/// others are retried individually, until the queue moves them to its
+/// dead-letter queue (if configured).
+///
+/// ```rust,ignore
+/// use restate_worker::QueueConsumer;
+/// use restate_worker::ingress::IngressClient;
+///
+/// #[event(queue)]
+/// async fn queue(batch: MessageBatch<serde_json::Value>, env: Env, _ctx: Context) -> Result<()> {
+///     QueueConsumer::new(IngressClient::from_env(&env)?, "Orders", "process")
+///         .consume(&batch)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueueConsumer<T = IngressTransport> {
+    client: IngressClient<T>,
+    service: String,
+    handler: String,
+    retry_delay: Option<Duration>,
+}
+
+impl<T: Transport> QueueConsumer<T> {
+    /// Creates a consumer sending messages to `handler` of `service`.
+    pub fn new(
+        client: IngressClient<T>,
+        service: impl Into<String>,
+        handler: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            service: service.into(),
+            handler: handler.into(),
+            retry_delay: None,
+        }
+    }
+
+    /// Delays the redelivery of messages rejected by the ingress, instead of
+    /// the retry delay configured for the queue.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = Some(delay);
+        self
+    }
+
+    /// Forwards every message of the batch, acknowledging or retrying each of
+    /// them depending on the response of the ingress.
+    ///
+    /// Fails, retrying the whole batch, only if the messages cannot be
+    /// deserialized.
+    pub async fn consume<M>(&self, batch: &MessageBatch<M>) -> Result<()>
+    where
+        M: Serialize + DeserializeOwned,
+    {
+        for message in batch.messages()? {
+            let id = message.id();
+
+            match self.deliver(&id, message.body()).await {
+                Outcome::Ack => message.ack(),
+                Outcome::Drop(error) => {
+                    console_error!(
+                        "dropping message {id} of queue {} rejected by {}/{}: {error}",
+                        batch.queue(),
+                        self.service,
+                        self.handler
+                    );
+
+                    message.ack();
+                }
+                Outcome::Retry { delay, error } => {
+                    console_error!(
+                        "cannot forward message {id} of queue {} to {}/{}: {error}",
+                        batch.queue(),
+                        self.service,
+                        self.handler
+                    );
+
+                    match delay {
+                        Some(delay) => message.retry_with_options(
+                            &QueueRetryOptionsBuilder::new()
+                                .with_delay_seconds(delay.as_secs().try_into().unwrap_or(u32::MAX))
+                                .build(),
+                        ),
+                        None => message.retry(),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forwards a single message, deciding whether to acknowledge or retry it.
+    async fn deliver<M: Serialize>(&self, id: &str, body: &M) -> Outcome {
+        match self.forward(id, body).await {
+            Ok(_) => Outcome::Ack,
+            Err(error) if !is_retryable(&error) => Outcome::Drop(error),
+            Err(error) => Outcome::Retry {
+                delay: self.retry_delay,
+                error,
+            },
+        }
+    }
+
+    /// Sends a single message to the handler, using its id as idempotency
+    /// key.
+    pub async fn forward<M: Serialize>(
+        &self,
+        id: &str,
+        body: &M,
+    ) -> std::result::Result<SendResponse, IngressError> {
+        self.client
+            .service(&self.service)
+            .handler(&self.handler)
+            .idempotency_key(id)
+            .send(body)
+            .await
+    }
+}
+
+/// Outcome of the delivery of a queue message to the ingress.
+#[derive(Debug)]
+enum Outcome {
+    /// The invocation was accepted, so the message is acknowledged.
+    Ack,
+    /// The message can never be accepted, so it is acknowledged as well
+    /// rather than retried until it reaches the dead-letter queue.
+    Drop(IngressError),
+    /// The ingress rejected the message, so it is retried, after `delay` if
+    /// set.
+    Retry {
+        delay: Option<Duration>,
+        error: IngressError,
+    },
+}
+
+/// Returns whether sending the message again may succeed.
+fn is_retryable(error: &IngressError) -> bool {
+    match error {
+        IngressError::Transport(_) => true,
+        IngressError::Status { status, .. } => {
+            !status.is_client_error()
+                || *status == StatusCode::REQUEST_TIMEOUT
+                || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        IngressError::InvalidRequest(_) | IngressError::Payload(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingress::MockIngress;
+    use futures::executor::block_on;
+
+    #[test]
+    fn forwards_message_with_idempotency_key() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let consumer = QueueConsumer::new(
+            IngressClient::with_transport("http://restate:8080", &ingress),
+            "Orders",
+            "process",
+        );
+
+        block_on(consumer.forward("msg-1", &serde_json::json!({"order": 1}))).unwrap();
+
+        let request = &ingress.requests()[0];
+        assert_eq!(request.uri(), "http://restate:8080/Orders/process/send");
+        assert_eq!(request.headers()["idempotency-key"], "msg-1");
+        assert_eq!(request.body(), r#"{"order":1}"#);
+    }
+
+    #[test]
+    fn acknowledges_accepted_message() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let consumer = QueueConsumer::new(
+            IngressClient::with_transport("http://restate:8080", &ingress),
+            "Orders",
+            "process",
+        )
+        .retry_delay(Duration::from_secs(30));
+
+        let outcome = block_on(consumer.deliver("msg-1", &1));
+
+        assert!(matches!(outcome, Outcome::Ack), "{outcome:?}");
+    }
+
+    #[test]
+    fn drops_message_that_cannot_be_accepted() {
+        let ingress =
+            MockIngress::new(|_| MockIngress::status(StatusCode::NOT_FOUND, "no service"));
+        let consumer = QueueConsumer::new(
+            IngressClient::with_transport("http://restate:8080", &ingress),
+            "Orders",
+            "process",
+        );
+
+        let outcome = block_on(consumer.deliver("msg-1", &1));
+        assert!(
+            matches!(&outcome, Outcome::Drop(error) if error.status() == Some(StatusCode::NOT_FOUND)),
+            "{outcome:?}"
+        );
+
+        let outcome = block_on(consumer.deliver("msg\n2", &2));
+        assert!(
+            matches!(outcome, Outcome::Drop(IngressError::InvalidRequest(_))),
+            "{outcome:?}"
+        );
+
+        for status in [StatusCode::REQUEST_TIMEOUT, StatusCode::TOO_MANY_REQUESTS] {
+            let ingress = MockIngress::new(move |_| MockIngress::status(status, "later"));
+            let consumer = QueueConsumer::new(
+                IngressClient::with_transport("http://restate:8080", &ingress),
+                "Orders",
+                "process",
+            );
+
+            let outcome = block_on(consumer.deliver("msg-3", &3));
+            assert!(matches!(outcome, Outcome::Retry { .. }), "{outcome:?}");
+        }
+    }
+
+    #[test]
+    fn retries_rejected_message() {
+        let ingress =
+            MockIngress::new(|_| MockIngress::status(StatusCode::SERVICE_UNAVAILABLE, "down"));
+        let consumer = QueueConsumer::new(
+            IngressClient::with_transport("http://restate:8080", &ingress),
+            "Orders",
+            "process",
+        );
+
+        let Outcome::Retry { delay, error } = block_on(consumer.deliver("msg-1", &1)) else {
+            panic!("message must be retried");
+        };
+        assert_eq!(delay, None);
+        assert_eq!(error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+
+        let consumer = consumer.retry_delay(Duration::from_secs(30));
+        let outcome = block_on(consumer.deliver("msg-2", &2));
+
+        assert!(
+            matches!(outcome, Outcome::Retry { delay: Some(delay), .. } if delay == Duration::from_secs(30)),
+            "{outcome:?}"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use bytes::Bytes;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::{HeaderMap, HeaderValue, Method};
+use worker::{Env, Error, Result, console_log};
+
+use crate::admin::{DEPLOYMENTS_PATH, RegisterDeployment, RegisteredDeployment};
+use crate::bindings;
+use crate::fetch::{self, JournaledResponse};
+use crate::version::{self, versioned_path};
+
+/// Name of the variable holding the URL of the Restate admin API.
+pub const ADMIN_URL_BINDING: &str = "RESTATE_ADMIN_URL";
+
+/// Name of the secret holding the token authenticating with the Restate admin
+/// API (e.g. a Restate Cloud API key).
+pub const ADMIN_TOKEN_BINDING: &str = "RESTATE_AUTH_TOKEN";
+
+/// Name of the variable holding the public URL of the worker serving the
+/// Restate endpoint, including any path prefix.
+pub const DEPLOYMENT_URL_BINDING: &str = "RESTATE_DEPLOYMENT_URL";
+
+/// Name of the KV namespace recording the registered worker versions.
+pub const REGISTRATIONS_KV_BINDING: &str = "RESTATE_REGISTRATIONS";
+
+/// Registers the running worker version with Restate.
+///
+/// Meant to be run from a `scheduled` event: when a new worker version is
+/// detected (see [`VERSION_METADATA_BINDING`](crate::VERSION_METADATA_BINDING)),
+/// its [versioned URL](versioned_path) is registered with the Restate admin
+/// API, and the version is recorded in KV so that it is registered only once.
+/// The handler must be [pinned](crate::HandlerBuilder::version_from_env) to
+/// the running version as well.
+///
+/// ```rust,ignore
+/// use restate_worker::SelfRegistration;
+///
+/// #[event(scheduled)]
+/// async fn scheduled(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+///     if let Err(e) = register(&env).await {
+///         console_error!("cannot register Restate deployment: {e}");
+///     }
+/// }
+///
+/// async fn register(env: &Env) -> Result<()> {
+///     SelfRegistration::from_env(env)?.register(env).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SelfRegistration {
+    admin_url: String,
+    admin_token: Option<String>,
+    deployment_url: String,
+    kv_binding: String,
+    force: bool,
+}
+
+impl SelfRegistration {
+    /// Creates a registration of the worker served at `deployment_url` with
+    /// the admin API at `admin_url`.
+    pub fn new(admin_url: impl Into<String>, deployment_url: impl Into<String>) -> Self {
+        Self {
+            admin_url: admin_url.into(),
+            admin_token: None,
+            deployment_url: deployment_url.into(),
+            kv_binding: REGISTRATIONS_KV_BINDING.to_owned(),
+            force: false,
+        }
+    }
+
+    /// Creates a registration configured from the worker environment.
+    ///
+    /// Reads the admin API URL from [`ADMIN_URL_BINDING`], the worker URL from
+    /// [`DEPLOYMENT_URL_BINDING`] and, if set, the admin API token from
+    /// [`ADMIN_TOKEN_BINDING`].
+    pub fn from_env(env: &Env) -> Result<Self> {
+        let mut registration = Self::new(
+            bindings::var(env, ADMIN_URL_BINDING)?,
+            bindings::var(env, DEPLOYMENT_URL_BINDING)?,
+        );
+        if let Ok(token) = env.secret(ADMIN_TOKEN_BINDING) {
+            registration = registration.admin_token(token.to_string());
+        }
+
+        Ok(registration)
+    }
+
+    /// Authenticates with the admin API using the given bearer token.
+    pub fn admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    /// Records the registered versions in the given KV namespace instead of
+    /// [`REGISTRATIONS_KV_BINDING`].
+    pub fn kv_binding(mut self, binding: impl Into<String>) -> Self {
+        self.kv_binding = binding.into();
+        self
+    }
+
+    /// Overrides an existing deployment registered at the same URL.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Registers the running worker version, unless it is already registered.
+    ///
+    /// Returns the registered deployment, or [`None`] if the version was
+    /// registered before.
+    pub async fn register(&self, env: &Env) -> Result<Option<RegisteredDeployment>> {
+        let version_id = version::version_id_from_env(env)?;
+        let kv = env.kv(&self.kv_binding)?;
+        let key = registration_key(&version_id);
+
+        if kv.get(&key).text().await?.is_some() {
+            return Ok(None);
+        }
+
+        let request = self.request(&version_id);
+        let url = format!("{}{DEPLOYMENTS_PATH}", self.admin_url.trim_end_matches('/'));
+        let body = serde_json::to_vec(&request).map_err(|e| Error::RustError(e.to_string()))?;
+
+        let response =
+            fetch::fetch(Method::POST, &url, &self.headers()?, &Bytes::from(body)).await?;
+        let deployment = registered(response)?;
+
+        kv.put(&key, deployment.id())?.execute().await?;
+        console_log!(
+            "registered Restate deployment {} at {}",
+            deployment.id(),
+            request.uri()
+        );
+
+        Ok(Some(deployment))
+    }
+
+    fn request(&self, version_id: &str) -> RegisterDeployment {
+        let uri = format!(
+            "{}{}",
+            self.deployment_url.trim_end_matches('/'),
+            versioned_path(version_id)
+        );
+
+        RegisterDeployment::new(uri).force(self.force)
+    }
+
+    fn headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        if let Some(token) = &self.admin_token {
+            let value = HeaderValue::try_from(format!("Bearer {token}"))
+                .map_err(|e| Error::RustError(format!("invalid Restate admin token: {e}")))?;
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Key recording the registration of a worker version.
+fn registration_key(version_id: &str) -> String {
+    format!("restate-deployment:{version_id}")
+}
+
+/// Reads the deployment registered by the admin API from its response.
+fn registered(response: JournaledResponse) -> Result<RegisteredDeployment> {
+    if !response.status().is_success() {
+        return Err(Error::RustError(format!(
+            "Restate admin API responded with status {}: {}",
+            response.status(),
+            response.text().unwrap_or_default()
+        )));
+    }
+
+    response
+        .json()
+        .map_err(|e| Error::RustError(format!("invalid Restate admin API response: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+
+    #[test]
+    fn registers_versioned_url() {
+        let registration = SelfRegistration::new("http://admin", "https://example.com/restate/");
+
+        let request = registration.request("abc");
+
+        assert_eq!(request.uri(), "https://example.com/restate/v/abc");
+        assert_eq!(registration_key("abc"), "restate-deployment:abc");
+    }
+
+    #[test]
+    fn reads_admin_response() {
+        let created = JournaledResponse::new(
+            StatusCode::CREATED,
+            HeaderMap::new(),
+            Bytes::from_static(br#"{"id": "dp_1", "services": []}"#),
+        );
+        assert_eq!(registered(created).unwrap().id(), "dp_1");
+
+        let conflict = JournaledResponse::new(
+            StatusCode::CONFLICT,
+            HeaderMap::new(),
+            Bytes::from_static(b"exists"),
+        );
+        let error = registered(conflict).unwrap_err().to_string();
+        assert!(error.contains("409 Conflict: exists"), "{error}");
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use worker::{Env, HttpRequest, Request, Response, Result, Router};
+
+use crate::Handler;
+use crate::bindings::Bindings;
+use crate::handler::{BoxError, ResponseBody};
+
+/// Mounts a [`Handler`] on a [`worker::Router`].
+///
+/// ```rust,ignore
+/// use restate_worker::RouterExt;
+/// use worker::Router;
+///
+/// Router::new()
+///     .get("/", |_, _| Response::ok("Hello"))
+///     .restate("/restate/*path", handler)
+///     .run(req, env)
+///     .await
+/// ```
+pub trait RouterExt {
+    /// Serves requests matching `pattern` (for any method) with the handler.
+    ///
+    /// The pattern must capture every path below the mount point with a
+    /// catch-all parameter (e.g. `/restate/*path`). The request is forwarded
+    /// as is: the Restate endpoint only looks at the trailing segments of the
+    /// path, so there is no need to strip the mount point.
+    ///
+    /// Restate handlers can access the worker environment of the route
+    /// through [`WorkerContextExt::worker_env`](crate::WorkerContextExt::worker_env).
+    /// Routes do not receive the execution context, so
+    /// [`WorkerContextExt::worker_context`](crate::WorkerContextExt::worker_context)
+    /// fails.
+    fn restate(self, pattern: &str, handler: Handler) -> Self;
+}
+
+impl<'a, D: 'a> RouterExt for Router<'a, D> {
+    fn restate(self, pattern: &str, handler: Handler) -> Self {
+        self.on_async(pattern, move |req, ctx| {
+            serve(handler.clone(), req, ctx.env)
+        })
+    }
+}
+
+async fn serve(handler: Handler, req: Request, env: Env) -> Result<Response> {
+    let req = HttpRequest::try_from(req)?;
+    let response = route(&handler, req, env).await;
+
+    handler.respond(response)?.try_into()
+}
+
+/// Serves a request converted from a [`worker::Request`], exposing the
+/// environment of the route to the invoked Restate handlers.
+async fn route<B>(
+    handler: &Handler,
+    req: http::Request<B>,
+    env: Env,
+) -> http::Response<ResponseBody>
+where
+    B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+{
+    let bindings = Arc::new(Bindings::from_env(env));
+
+    handler.serve(req, Some(bindings)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WorkerContextExt;
+    use crate::handler::tests::{invocation, read};
+    use http::StatusCode;
+    use http_body_util::Empty;
+    use restate_sdk::prelude::*;
+    use worker::wasm_bindgen::{JsCast, JsValue};
+
+    #[restate_sdk::service]
+    trait Bindings {
+        async fn context() -> HandlerResult<String>;
+    }
+
+    struct BindingsImpl;
+
+    impl Bindings for BindingsImpl {
+        async fn context(&self, ctx: Context<'_>) -> HandlerResult<String> {
+            Ok(ctx.worker_context().unwrap_err().to_string())
+        }
+    }
+
+    fn handler() -> Handler {
+        Handler::new(Endpoint::builder().bind(BindingsImpl.serve()).build())
+    }
+
+    // Placeholder environment: it must not be cloned, as that would call into JavaScript.
+    fn route<B>(handler: &Handler, req: http::Request<B>) -> (StatusCode, String)
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+    {
+        let env = JsValue::UNDEFINED.unchecked_into();
+
+        read(futures::executor::block_on(super::route(handler, req, env)))
+    }
+
+    #[test]
+    fn forwards_mounted_path() {
+        // Converted requests carry the full URL of the worker.
+        let req = http::Request::get("https://example.com/restate/discover")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+
+        let (status, body) = route(&handler(), req);
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("REQUEST_RESPONSE"), "{body}");
+    }
+
+    #[test]
+    fn exposes_route_environment() {
+        let req = invocation("https://example.com/restate/invoke/Bindings/context");
+
+        let (status, body) = route(&handler(), req);
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body.contains("worker execution context is not available"),
+            "{body}"
+        );
+    }
+}
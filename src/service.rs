@@ -0,0 +1,52 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::{Request, Response};
+use tower_service::Service;
+use worker::{Body, Error};
+
+use crate::Handler;
+
+/// Serves requests with [`Handler::handle`], so that the handler can be
+/// composed with tower middleware.
+///
+/// The handler is always ready, and the returned future owns a clone of the
+/// handler, which only clones a reference-counted pointer.
+impl Service<Request<Body>> for Handler {
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let handler = self.clone();
+
+        Box::pin(async move { handler.handle(req).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restate_sdk::prelude::Endpoint;
+
+    fn assert_service<S>(_service: &S)
+    where
+        S: Service<Request<Body>, Future: Send + 'static> + Clone + Send + Sync + 'static,
+    {
+    }
+
+    #[test]
+    fn handler_is_service() {
+        let mut handler = Handler::new(Endpoint::builder().build());
+        assert_service(&handler);
+
+        let waker = std::task::Waker::noop();
+        let ready = handler.poll_ready(&mut Context::from_waker(waker));
+
+        assert!(matches!(ready, Poll::Ready(Ok(()))));
+    }
+}
@@ -0,0 +1,98 @@
+use http::Uri;
+use worker::{Env, Error, Result, WorkerVersionMetadata};
+
+use crate::handler::strip_path_prefix;
+
+/// Name of the [version metadata](https://developers.cloudflare.com/workers/runtime-apis/bindings/version-metadata/)
+/// binding identifying the version of the running worker.
+pub const VERSION_METADATA_BINDING: &str = "CF_VERSION_METADATA";
+
+/// Path segment preceding the version identifier in version-pinned URLs.
+const VERSION_SEGMENT: &str = "/v";
+
+/// Reads the identifier of the running worker version from
+/// [`VERSION_METADATA_BINDING`].
+pub(crate) fn version_id_from_env(env: &Env) -> Result<String> {
+    let metadata: WorkerVersionMetadata =
+        env.get_binding(VERSION_METADATA_BINDING).map_err(|e| {
+            Error::RustError(format!(
+                "cannot read worker version metadata from '{VERSION_METADATA_BINDING}': {e}"
+            ))
+        })?;
+
+    Ok(metadata.id())
+}
+
+/// Returns the path under which the given worker version serves the Restate
+/// endpoint (e.g. `/v/{version_id}`).
+///
+/// Register this path (after the worker URL and any path prefix) as the
+/// deployment URL, so that Restate only ever sends an invocation to the
+/// version that started it.
+pub fn versioned_path(version_id: &str) -> String {
+    format!("{VERSION_SEGMENT}/{version_id}")
+}
+
+/// Outcome of matching a request path against a version-pinned URL.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Pinned {
+    /// The request targets the given version; holds the URI without the
+    /// version segments.
+    Match(Uri),
+    /// The request targets another version.
+    Mismatch(String),
+    /// The request path is not version-pinned.
+    Unpinned,
+}
+
+/// Strips the `/v/{version_id}` segments from the path of `uri`, checking
+/// that they match `version_id`.
+pub(crate) fn strip_version(uri: &Uri, version_id: &str) -> Pinned {
+    let Some(rest) = strip_path_prefix(uri, VERSION_SEGMENT) else {
+        return Pinned::Unpinned;
+    };
+
+    let requested = rest.path()[1..].split('/').next().unwrap_or_default();
+    if requested.is_empty() {
+        return Pinned::Unpinned;
+    }
+
+    if requested != version_id {
+        return Pinned::Mismatch(requested.to_owned());
+    }
+
+    match strip_path_prefix(&rest, &format!("/{requested}")) {
+        Some(uri) => Pinned::Match(uri),
+        None => Pinned::Unpinned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_matching_version() {
+        let uri = Uri::from_static("https://example.com/v/abc/invoke/Greeter/greet?x=1");
+
+        assert_eq!(
+            strip_version(&uri, "abc"),
+            Pinned::Match(Uri::from_static(
+                "https://example.com/invoke/Greeter/greet?x=1"
+            ))
+        );
+        assert_eq!(
+            strip_version(&uri, "def"),
+            Pinned::Mismatch("abc".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_unpinned_paths() {
+        for uri in ["/discover", "/v", "/v/", "/vx/abc/discover"] {
+            let uri = Uri::from_static(uri);
+
+            assert_eq!(strip_version(&uri, "abc"), Pinned::Unpinned, "{uri}");
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Receivers of third-party webhooks (Stripe, GitHub, or any provider signing
+//! them with HMAC-SHA256 or Ed25519), forwarding them to Restate through the
+//! ingress once their signature is verified.
+//!
+//! [`AwakeableWebhook`] completes the awakeable a handler is waiting on, while
+//! [`IngestionWebhook`] starts a workflow or invokes a virtual object for
+//! every webhook.
+//!
+//! Webhooks are served next to the [`Handler`](crate::Handler) in the same
+//! worker:
+//!
+//! ```rust,ignore
+//! use restate_worker::ingress::IngressClient;
+//! use restate_worker::webhook::{AwakeableWebhook, Field, Stripe};
+//!
+//! #[event(fetch)]
+//! async fn fetch(req: HttpRequest, env: Env, ctx: Context) -> Result<HttpResponse> {
+//!     if req.uri().path() == "/webhooks/stripe" {
+//!         let secret = env.secret("STRIPE_WEBHOOK_SECRET")?.to_string();
+//!         let webhook = AwakeableWebhook::new(
+//!             IngressClient::from_env(&env)?,
+//!             Stripe::new(&secret),
+//!             Field::json("/data/object/metadata/awakeable_id"),
+//!         );
+//!
+//!         return webhook.handle(req).await;
+//!     }
+//!
+//!     HANDLER.get_or_init(&env)?.handle_with_env(req, env, ctx).await
+//! }
+//! ```
+
+use bytes::Bytes;
+use http::header::CONTENT_TYPE;
+use http::request::Parts;
+use http::{Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use serde_json::Value;
+
+use crate::ingress::IngressError;
+
+mod awakeable;
+mod ingest;
+mod signature;
+
+pub use awakeable::{AwakeableWebhook, Completion};
+pub use ingest::IngestionWebhook;
+pub use signature::{Ed25519, Encoding, GitHub, Hmac, SignatureError, Stripe, Timestamp, Verifier};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors returned when a webhook cannot be forwarded to Restate.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum WebhookError {
+    /// The signature of the webhook is invalid.
+    #[error("invalid webhook signature: {0}")]
+    Signature(#[from] SignatureError),
+
+    /// The webhook body cannot be read or is not valid JSON.
+    #[error("invalid webhook payload: {0}")]
+    Payload(String),
+
+    /// The webhook does not contain a required field.
+    #[error("webhook does not contain {0}")]
+    MissingField(Field),
+
+    /// The Restate ingress rejected the invocation.
+    #[error("cannot forward webhook to Restate: {0}")]
+    Ingress(#[from] IngressError),
+}
+
+impl WebhookError {
+    /// Returns the HTTP status code for this error.
+    ///
+    /// Providers retry webhooks that fail with a 5xx status code, so only
+    /// failures of the Restate ingress map to one.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            WebhookError::Signature(_) => StatusCode::UNAUTHORIZED,
+            WebhookError::Payload(_) | WebhookError::MissingField(_) => StatusCode::BAD_REQUEST,
+            WebhookError::Ingress(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn to_response(&self) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(self.status_code())
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Full::new(Bytes::from(self.to_string())))
+            .expect("headers must be valid")
+    }
+}
+
+/// Location of a value in a webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    /// Value of a header.
+    Header(String),
+    /// Value of a query parameter, as sent (without percent-decoding).
+    Query(String),
+    /// String or number in the JSON payload, identified by a
+    /// [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901) (e.g.
+    /// `/data/object/id`).
+    Json(String),
+}
+
+impl Field {
+    /// Locates the value of a header.
+    pub fn header(name: impl Into<String>) -> Self {
+        Field::Header(name.into())
+    }
+
+    /// Locates the value of a query parameter.
+    pub fn query(name: impl Into<String>) -> Self {
+        Field::Query(name.into())
+    }
+
+    /// Locates a value of the JSON payload by its JSON pointer.
+    pub fn json(pointer: impl Into<String>) -> Self {
+        Field::Json(pointer.into())
+    }
+
+    /// Extracts the value from a webhook.
+    fn extract(&self, webhook: &Webhook) -> Result<String, WebhookError> {
+        let value = match self {
+            Field::Header(name) => webhook
+                .parts
+                .headers
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+            Field::Query(name) => webhook.parts.uri.query().and_then(|query| {
+                query
+                    .split('&')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.to_owned())
+            }),
+            Field::Json(pointer) => match webhook.payload.pointer(pointer) {
+                Some(Value::String(value)) => Some(value.clone()),
+                Some(Value::Number(value)) => Some(value.to_string()),
+                _ => None,
+            },
+        };
+
+        value
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| WebhookError::MissingField(self.clone()))
+    }
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Header(name) => write!(f, "header '{name}'"),
+            Field::Query(name) => write!(f, "query parameter '{name}'"),
+            Field::Json(pointer) => write!(f, "field '{pointer}'"),
+        }
+    }
+}
+
+/// Webhook whose signature has been verified.
+struct Webhook {
+    parts: Parts,
+    payload: Value,
+}
+
+impl Webhook {
+    /// Reads a webhook, verifying its signature.
+    async fn read<B>(
+        req: Request<B>,
+        verifier: &dyn Verifier,
+        now: u64,
+    ) -> Result<Self, WebhookError>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        let (parts, body) = req.into_parts();
+        let body = body
+            .collect()
+            .await
+            .map_err(|e| WebhookError::Payload(e.into().to_string()))?
+            .to_bytes();
+
+        verifier.verify(&parts.headers, &body, now)?;
+
+        let payload =
+            serde_json::from_slice(&body).map_err(|e| WebhookError::Payload(e.to_string()))?;
+
+        Ok(Self { parts, payload })
+    }
+}
+
+/// Responds to the provider of a webhook.
+fn respond(result: Result<(), WebhookError>) -> Response<Full<Bytes>> {
+    match result {
+        Ok(()) => Response::new(Full::default()),
+        Err(e) => e.to_response(),
+    }
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+fn now() -> u64 {
+    worker::Date::now().as_millis() / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    struct Unsigned;
+
+    impl Verifier for Unsigned {
+        fn verify(&self, _: &http::HeaderMap, _: &[u8], _: u64) -> Result<(), SignatureError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn extracts_fields() {
+        let req = Request::builder()
+            .uri("/webhook?id=abc&other=1")
+            .header("x-id", "def")
+            .body(Full::new(Bytes::from_static(br#"{"data": {"id": 42}}"#)))
+            .unwrap();
+        let webhook = block_on(Webhook::read(req, &Unsigned, 0)).unwrap();
+
+        assert_eq!(Field::query("id").extract(&webhook).unwrap(), "abc");
+        assert_eq!(Field::header("X-Id").extract(&webhook).unwrap(), "def");
+        assert_eq!(Field::json("/data/id").extract(&webhook).unwrap(), "42");
+
+        let error = Field::json("/data/key").extract(&webhook).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "webhook does not contain field '/data/key'"
+        );
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{Request, Response};
+use http_body_util::Full;
+use serde_json::Value;
+use worker::{Body, Result};
+
+use super::{BoxError, Field, Verifier, Webhook, WebhookError};
+use crate::handler::into_worker_response;
+use crate::ingress::{IngressClient, IngressTransport, Transport};
+
+/// Outcome of a webhook for the awakeable it targets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Completion {
+    /// Resolves the awakeable with the given value.
+    Resolve(Value),
+    /// Rejects the awakeable with the given reason.
+    Reject(String),
+    /// Acknowledges the webhook without completing the awakeable (e.g. for
+    /// events the awakeable does not wait for).
+    Ignore,
+}
+
+/// Webhook endpoint completing Restate awakeables.
+///
+/// Verifies the signature of each webhook, extracts the id of the awakeable
+/// it targets (e.g. from metadata attached to a payment when the handler
+/// created it), and resolves the awakeable with the webhook payload through
+/// the Restate ingress. Use [`complete_with`](Self::complete_with) to decide
+/// how to complete the awakeable based on the payload.
+///
+/// Responds with `401 Unauthorized` to webhooks with an invalid signature,
+/// `400 Bad Request` to webhooks without an awakeable id, and
+/// `502 Bad Gateway` if the ingress fails, so that the provider retries.
+///
+/// ```rust,ignore
+/// use restate_worker::webhook::{AwakeableWebhook, Completion, Field, Stripe};
+///
+/// let webhook = AwakeableWebhook::new(client, Stripe::new(&secret), Field::json("/data/object/metadata/awakeable_id"))
+///     .complete_with(|event| match event["type"].as_str() {
+///         Some("payment_intent.succeeded") => Completion::Resolve(event["data"]["object"].clone()),
+///         Some("payment_intent.payment_failed") => Completion::Reject("payment failed".to_owned()),
+///         _ => Completion::Ignore,
+///     });
+/// ```
+#[derive(Clone)]
+pub struct AwakeableWebhook<T = IngressTransport> {
+    client: IngressClient<T>,
+    verifier: Arc<dyn Verifier + Send + Sync>,
+    awakeable_id: Field,
+    complete: Arc<dyn Fn(&Value) -> Completion + Send + Sync>,
+}
+
+impl<T: Transport> AwakeableWebhook<T> {
+    /// Creates an endpoint verifying webhooks with `verifier` and resolving
+    /// the awakeable identified by `awakeable_id` with their payload.
+    pub fn new(
+        client: IngressClient<T>,
+        verifier: impl Verifier + Send + Sync + 'static,
+        awakeable_id: Field,
+    ) -> Self {
+        Self {
+            client,
+            verifier: Arc::new(verifier),
+            awakeable_id,
+            complete: Arc::new(|payload| Completion::Resolve(payload.clone())),
+        }
+    }
+
+    /// Decides how to complete the awakeable based on the webhook payload.
+    pub fn complete_with(
+        mut self,
+        complete: impl Fn(&Value) -> Completion + Send + Sync + 'static,
+    ) -> Self {
+        self.complete = Arc::new(complete);
+        self
+    }
+
+    /// Processes a webhook, responding to its provider.
+    pub async fn handle<B>(&self, req: Request<B>) -> Result<Response<Body>>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        into_worker_response(self.serve(req, super::now()).await)
+    }
+
+    async fn serve<B>(&self, req: Request<B>, now: u64) -> Response<Full<Bytes>>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        super::respond(self.complete(req, now).await)
+    }
+
+    async fn complete<B>(&self, req: Request<B>, now: u64) -> std::result::Result<(), WebhookError>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        let webhook = Webhook::read(req, self.verifier.as_ref(), now).await?;
+
+        let completion = (self.complete)(&webhook.payload);
+        if completion == Completion::Ignore {
+            return Ok(());
+        }
+
+        let id = self.awakeable_id.extract(&webhook)?;
+        match completion {
+            Completion::Resolve(value) => self.client.resolve_awakeable(&id, &value).await?,
+            Completion::Reject(reason) => self.client.reject_awakeable(&id, &reason).await?,
+            Completion::Ignore => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingress::MockIngress;
+    use crate::webhook::GitHub;
+    use crate::webhook::signature::tests::sign;
+    use futures::executor::block_on;
+    use http::StatusCode;
+    use http_body_util::BodyExt;
+
+    fn webhook(body: &'static str, signature: &str) -> Request<Full<Bytes>> {
+        Request::post("/webhooks/github?awakeable=sign_1")
+            .header("x-hub-signature-256", format!("sha256={signature}"))
+            .body(Full::new(Bytes::from_static(body.as_bytes())))
+            .unwrap()
+    }
+
+    fn read(response: Response<Full<Bytes>>) -> (StatusCode, String) {
+        let status = response.status();
+        let body = block_on(response.into_body().collect()).unwrap().to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn completes_awakeables() {
+        let ingress = MockIngress::new(|_| MockIngress::status(StatusCode::ACCEPTED, ""));
+        let endpoint = AwakeableWebhook::new(
+            IngressClient::with_transport("http://restate", &ingress),
+            GitHub::new("secret"),
+            Field::query("awakeable"),
+        )
+        .complete_with(|payload| match payload["action"].as_str() {
+            Some("approved") => Completion::Resolve(payload["review"].clone()),
+            Some("rejected") => Completion::Reject("review rejected".to_owned()),
+            _ => Completion::Ignore,
+        });
+
+        for body in [
+            r#"{"action": "approved", "review": {"id": 1}}"#,
+            r#"{"action": "rejected"}"#,
+            r#"{"action": "opened"}"#,
+        ] {
+            let response =
+                block_on(endpoint.serve(webhook(body, &sign(b"secret", body.as_bytes())), 0));
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let requests = ingress.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].uri().path(),
+            "/restate/awakeables/sign_1/resolve"
+        );
+        assert_eq!(requests[0].body(), r#"{"id":1}"#);
+        assert_eq!(
+            requests[1].uri().path(),
+            "/restate/awakeables/sign_1/reject"
+        );
+        assert_eq!(requests[1].body(), "review rejected");
+    }
+
+    #[test]
+    fn rejects_invalid_webhooks() {
+        let ingress = MockIngress::new(|_| MockIngress::status(StatusCode::ACCEPTED, ""));
+        let endpoint = AwakeableWebhook::new(
+            IngressClient::with_transport("http://restate", &ingress),
+            GitHub::new("secret"),
+            Field::json("/awakeable_id"),
+        );
+
+        let (status, body) = read(block_on(endpoint.serve(webhook("{}", "00"), 0)));
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "invalid webhook signature: signature mismatch");
+
+        let (status, _) = read(block_on(
+            endpoint.serve(webhook("{}", &sign(b"secret", b"{}")), 0),
+        ));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        assert!(ingress.requests().is_empty());
+    }
+}
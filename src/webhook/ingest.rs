@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{Request, Response};
+use http_body_util::Full;
+use worker::{Body, Result};
+
+use super::{BoxError, Field, Verifier, Webhook, WebhookError};
+use crate::handler::into_worker_response;
+use crate::ingress::{IngressClient, IngressTransport, Transport};
+
+/// Restate handler receiving the webhooks.
+#[derive(Debug, Clone)]
+enum Target {
+    Workflow(String),
+    Object { name: String, handler: String },
+}
+
+/// Webhook endpoint starting a Restate workflow, or sending to a virtual
+/// object, for every webhook.
+///
+/// Verifies the signature of each webhook, then sends its payload to the
+/// workflow or virtual object keyed by a field of the webhook (e.g. the
+/// customer id). Invocations of virtual objects are sent with the event id
+/// of the provider as idempotency key, so that webhooks delivered more than
+/// once invoke the handler only once. Workflows are deduplicated by their id.
+///
+/// Responds with `401 Unauthorized` to webhooks with an invalid signature,
+/// `400 Bad Request` to webhooks without key or event id, and
+/// `502 Bad Gateway` if the ingress fails, so that the provider retries.
+///
+/// ```rust,ignore
+/// use restate_worker::webhook::{Field, GitHub, IngestionWebhook};
+///
+/// let webhook = IngestionWebhook::object(client, GitHub::new(&secret), "Repository", "on_event", Field::json("/repository/full_name"))
+///     .event_id(Field::header("x-github-delivery"));
+/// ```
+#[derive(Clone)]
+pub struct IngestionWebhook<T = IngressTransport> {
+    client: IngressClient<T>,
+    verifier: Arc<dyn Verifier + Send + Sync>,
+    target: Target,
+    key: Field,
+    event_id: Field,
+}
+
+impl<T: Transport> IngestionWebhook<T> {
+    /// Creates an endpoint starting the `workflow` with the id found in the
+    /// `key` field of each webhook.
+    pub fn workflow(
+        client: IngressClient<T>,
+        verifier: impl Verifier + Send + Sync + 'static,
+        workflow: impl Into<String>,
+        key: Field,
+    ) -> Self {
+        Self::new(client, verifier, Target::Workflow(workflow.into()), key)
+    }
+
+    /// Creates an endpoint sending each webhook to `handler` of the virtual
+    /// `object` with the key found in the `key` field of the webhook.
+    pub fn object(
+        client: IngressClient<T>,
+        verifier: impl Verifier + Send + Sync + 'static,
+        object: impl Into<String>,
+        handler: impl Into<String>,
+        key: Field,
+    ) -> Self {
+        let target = Target::Object {
+            name: object.into(),
+            handler: handler.into(),
+        };
+
+        Self::new(client, verifier, target, key)
+    }
+
+    fn new(
+        client: IngressClient<T>,
+        verifier: impl Verifier + Send + Sync + 'static,
+        target: Target,
+        key: Field,
+    ) -> Self {
+        Self {
+            client,
+            verifier: Arc::new(verifier),
+            target,
+            key,
+            event_id: Field::json("/id"),
+        }
+    }
+
+    /// Reads the event id of the provider from the given field, instead of
+    /// the `id` field of the payload (as sent by Stripe).
+    pub fn event_id(mut self, event_id: Field) -> Self {
+        self.event_id = event_id;
+        self
+    }
+
+    /// Processes a webhook, responding to its provider.
+    pub async fn handle<B>(&self, req: Request<B>) -> Result<Response<Body>>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        into_worker_response(self.serve(req, super::now()).await)
+    }
+
+    async fn serve<B>(&self, req: Request<B>, now: u64) -> Response<Full<Bytes>>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        super::respond(self.ingest(req, now).await)
+    }
+
+    async fn ingest<B>(&self, req: Request<B>, now: u64) -> std::result::Result<(), WebhookError>
+    where
+        B: http_body::Body<Data = Bytes, Error: Into<BoxError>>,
+    {
+        let webhook = Webhook::read(req, self.verifier.as_ref(), now).await?;
+        let key = self.key.extract(&webhook)?;
+
+        let invocation = match &self.target {
+            Target::Workflow(name) => self.client.workflow(name, &key).run(),
+            Target::Object { name, handler } => {
+                let event_id = self.event_id.extract(&webhook)?;
+
+                self.client
+                    .object(name, &key)
+                    .handler(handler)
+                    .idempotency_key(&event_id)
+            }
+        };
+
+        invocation.send(&webhook.payload).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingress::MockIngress;
+    use crate::webhook::signature::tests::sign;
+    use crate::webhook::{Hmac, Timestamp};
+    use futures::executor::block_on;
+    use http::{HeaderName, StatusCode};
+    use std::time::Duration;
+
+    fn verifier() -> Hmac {
+        Hmac::sha256(HeaderName::from_static("x-signature"), b"secret").timestamp(
+            Timestamp::new(
+                HeaderName::from_static("x-timestamp"),
+                Duration::from_secs(300),
+            )
+            .separator("."),
+        )
+    }
+
+    fn webhook(body: &'static str) -> Request<Full<Bytes>> {
+        let signature = sign(b"secret", format!("1700000000.{body}").as_bytes());
+
+        Request::post("/webhooks/orders")
+            .header("x-signature", signature)
+            .header("x-timestamp", "1700000000")
+            .header("x-event-id", "evt_1")
+            .body(Full::new(Bytes::from_static(body.as_bytes())))
+            .unwrap()
+    }
+
+    #[test]
+    fn sends_to_object_with_event_id() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let endpoint = IngestionWebhook::object(
+            IngressClient::with_transport("http://restate", &ingress),
+            verifier(),
+            "Customer",
+            "on_order",
+            Field::json("/customer"),
+        )
+        .event_id(Field::header("x-event-id"));
+
+        let body = r#"{"customer": "cus_1", "total": 42}"#;
+        let response = block_on(endpoint.serve(webhook(body), 1_700_000_010));
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = &ingress.requests()[0];
+        assert_eq!(request.uri().path(), "/Customer/cus_1/on_order/send");
+        assert_eq!(request.headers()["idempotency-key"], "evt_1");
+        assert_eq!(request.body(), r#"{"customer":"cus_1","total":42}"#);
+
+        let response = block_on(endpoint.serve(webhook(body), 1_700_000_301));
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn starts_workflow() {
+        let ingress = MockIngress::new(MockIngress::accepted);
+        let endpoint = IngestionWebhook::workflow(
+            IngressClient::with_transport("http://restate", &ingress),
+            verifier(),
+            "Onboarding",
+            Field::json("/data/user"),
+        );
+
+        let response =
+            block_on(endpoint.serve(webhook(r#"{"data": {"user": "u1"}}"#), 1_700_000_000));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            ingress.requests()[0].uri().path(),
+            "/Onboarding/u1/run/send"
+        );
+
+        let response = block_on(endpoint.serve(webhook(r#"{"data": {}}"#), 1_700_000_000));
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,494 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use http::{HeaderMap, HeaderName};
+use ring::hmac;
+use ring::signature::{ED25519, UnparsedPublicKey};
+
+/// Header holding the signature of Stripe webhooks.
+const STRIPE_SIGNATURE: HeaderName = HeaderName::from_static("stripe-signature");
+
+/// Header holding the signature of GitHub webhooks.
+const GITHUB_SIGNATURE: HeaderName = HeaderName::from_static("x-hub-signature-256");
+
+/// Maximum age of Stripe webhooks accepted by default, as recommended by
+/// Stripe.
+const STRIPE_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// Errors returned when the signature of a webhook cannot be verified.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SignatureError {
+    /// The header holding the signature is missing.
+    #[error("missing '{0}' header")]
+    Missing(HeaderName),
+
+    /// The signature header cannot be parsed.
+    #[error("malformed '{0}' header")]
+    Malformed(HeaderName),
+
+    /// The signature does not match the webhook.
+    #[error("signature mismatch")]
+    Mismatch,
+
+    /// The webhook was signed too long ago (or too far in the future),
+    /// possibly replayed.
+    #[error("timestamp outside of the tolerance")]
+    Expired,
+}
+
+/// Verifies that a webhook was sent by its provider.
+pub trait Verifier {
+    /// Verifies the signature of a webhook with the given headers and body,
+    /// received at `now` (in seconds since the Unix epoch).
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: u64) -> Result<(), SignatureError>;
+}
+
+/// Verifies [Stripe webhook signatures](https://docs.stripe.com/webhooks#verify-events).
+#[derive(Debug, Clone)]
+pub struct Stripe {
+    key: hmac::Key,
+    tolerance: Duration,
+}
+
+impl Stripe {
+    /// Creates a verifier with the signing secret of the webhook endpoint
+    /// (`whsec_...`).
+    pub fn new(secret: &str) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+            tolerance: STRIPE_TOLERANCE,
+        }
+    }
+
+    /// Rejects webhooks signed longer than `tolerance` ago, instead of five
+    /// minutes.
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl Verifier for Stripe {
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: u64) -> Result<(), SignatureError> {
+        let header = header(headers, &STRIPE_SIGNATURE)?;
+        let malformed = || SignatureError::Malformed(STRIPE_SIGNATURE);
+
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for item in header.split(',') {
+            match item.trim().split_once('=') {
+                Some(("t", value)) => timestamp = Some(value),
+                // Several signatures are sent while the secret is rolled.
+                Some(("v1", value)) => signatures.push(decode_hex(value).ok_or_else(malformed)?),
+                _ => {}
+            }
+        }
+
+        let timestamp = timestamp.ok_or_else(malformed)?;
+        check_timestamp(
+            timestamp.parse().map_err(|_| malformed())?,
+            now,
+            self.tolerance,
+        )?;
+
+        let content = [timestamp.as_bytes(), b".", body].concat();
+        signatures
+            .iter()
+            .any(|signature| hmac::verify(&self.key, &content, signature).is_ok())
+            .then_some(())
+            .ok_or(SignatureError::Mismatch)
+    }
+}
+
+/// Verifies [GitHub webhook signatures](https://docs.github.com/en/webhooks/using-webhooks/validating-webhook-deliveries).
+#[derive(Debug, Clone)]
+pub struct GitHub(Hmac);
+
+impl GitHub {
+    /// Creates a verifier with the secret of the webhook.
+    pub fn new(secret: &str) -> Self {
+        Self(Hmac::sha256(GITHUB_SIGNATURE, secret.as_bytes()).prefix("sha256="))
+    }
+}
+
+impl Verifier for GitHub {
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: u64) -> Result<(), SignatureError> {
+        self.0.verify(headers, body, now)
+    }
+}
+
+/// Encoding of a signature (or key) in a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// Lowercase or uppercase hexadecimal.
+    Hex,
+    /// Standard base64, with padding.
+    Base64,
+}
+
+impl Encoding {
+    /// Decodes a value, returning [`None`] if it is not properly encoded.
+    pub fn decode(self, value: &str) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Hex => decode_hex(value),
+            Encoding::Base64 => STANDARD.decode(value).ok(),
+        }
+    }
+}
+
+/// Timestamp header sent along with signatures, protecting against replayed
+/// webhooks.
+///
+/// When set, the signed content is the timestamp, followed by the separator
+/// and the body (e.g. `{timestamp}.{body}`).
+#[derive(Debug, Clone)]
+pub struct Timestamp {
+    header: HeaderName,
+    tolerance: Duration,
+    separator: String,
+}
+
+impl Timestamp {
+    /// Reads the timestamp (in seconds since the Unix epoch) from `header`,
+    /// rejecting webhooks signed more than `tolerance` ago.
+    pub fn new(header: HeaderName, tolerance: Duration) -> Self {
+        Self {
+            header,
+            tolerance,
+            separator: String::new(),
+        }
+    }
+
+    /// Separates the timestamp from the body in the signed content.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Checks the timestamp of a webhook, returning the signed content.
+    fn signed_content(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Vec<u8>, SignatureError> {
+        let timestamp = header(headers, &self.header)?;
+        let seconds = timestamp
+            .parse()
+            .map_err(|_| SignatureError::Malformed(self.header.clone()))?;
+        check_timestamp(seconds, now, self.tolerance)?;
+
+        Ok([timestamp.as_bytes(), self.separator.as_bytes(), body].concat())
+    }
+}
+
+/// Header holding a signature of the webhook.
+#[derive(Debug, Clone)]
+struct SignatureHeader {
+    name: HeaderName,
+    prefix: String,
+    encoding: Encoding,
+    timestamp: Option<Timestamp>,
+}
+
+impl SignatureHeader {
+    fn new(name: HeaderName) -> Self {
+        Self {
+            name,
+            prefix: String::new(),
+            encoding: Encoding::Hex,
+            timestamp: None,
+        }
+    }
+
+    /// Reads the signature of a webhook, returning it along with the signed
+    /// content.
+    fn read<'a>(
+        &self,
+        headers: &HeaderMap,
+        body: &'a [u8],
+        now: u64,
+    ) -> Result<(Vec<u8>, Cow<'a, [u8]>), SignatureError> {
+        let signature = header(headers, &self.name)?
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|signature| self.encoding.decode(signature))
+            .ok_or_else(|| SignatureError::Malformed(self.name.clone()))?;
+
+        let content = match &self.timestamp {
+            Some(timestamp) => Cow::Owned(timestamp.signed_content(headers, body, now)?),
+            None => Cow::Borrowed(body),
+        };
+
+        Ok((signature, content))
+    }
+}
+
+/// Verifies HMAC-SHA256 signatures of the webhook body sent in a header.
+///
+/// ```rust,ignore
+/// use restate_worker::webhook::{Encoding, Hmac};
+///
+/// let verifier = Hmac::sha256(HeaderName::from_static("x-signature"), secret.as_bytes())
+///     .prefix("sha256=")
+///     .encoding(Encoding::Base64);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Hmac {
+    key: hmac::Key,
+    header: SignatureHeader,
+}
+
+impl Hmac {
+    /// Creates a verifier of hex-encoded signatures sent in `header`, keyed
+    /// with `secret`.
+    pub fn sha256(header: HeaderName, secret: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+            header: SignatureHeader::new(header),
+        }
+    }
+
+    /// Strips the given prefix (e.g. `sha256=`) from the header before
+    /// decoding the signature.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.header.prefix = prefix.into();
+        self
+    }
+
+    /// Sets the encoding of the signature.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.header.encoding = encoding;
+        self
+    }
+
+    /// Signs the body along with a timestamp, checked against a tolerance.
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.header.timestamp = Some(timestamp);
+        self
+    }
+}
+
+impl Verifier for Hmac {
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: u64) -> Result<(), SignatureError> {
+        let (signature, content) = self.header.read(headers, body, now)?;
+
+        hmac::verify(&self.key, &content, &signature).map_err(|_| SignatureError::Mismatch)
+    }
+}
+
+/// Verifies Ed25519 signatures of the webhook body sent in a header (e.g.
+/// by Discord).
+///
+/// ```rust,ignore
+/// use restate_worker::webhook::{Ed25519, Encoding, Timestamp};
+///
+/// let public_key = Encoding::Hex.decode(&public_key).expect("public key must be hex-encoded");
+/// let verifier = Ed25519::new(HeaderName::from_static("x-signature-ed25519"), public_key)
+///     .timestamp(Timestamp::new(HeaderName::from_static("x-signature-timestamp"), Duration::from_secs(300)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ed25519 {
+    public_key: Vec<u8>,
+    header: SignatureHeader,
+}
+
+impl Ed25519 {
+    /// Creates a verifier of hex-encoded signatures sent in `header`, made
+    /// with the private key matching `public_key`.
+    pub fn new(header: HeaderName, public_key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            public_key: public_key.into(),
+            header: SignatureHeader::new(header),
+        }
+    }
+
+    /// Strips the given prefix from the header before decoding the
+    /// signature.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.header.prefix = prefix.into();
+        self
+    }
+
+    /// Sets the encoding of the signature.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.header.encoding = encoding;
+        self
+    }
+
+    /// Signs the body along with a timestamp, checked against a tolerance.
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.header.timestamp = Some(timestamp);
+        self
+    }
+}
+
+impl Verifier for Ed25519 {
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: u64) -> Result<(), SignatureError> {
+        let (signature, content) = self.header.read(headers, body, now)?;
+
+        UnparsedPublicKey::new(&ED25519, &self.public_key)
+            .verify(&content, &signature)
+            .map_err(|_| SignatureError::Mismatch)
+    }
+}
+
+/// Reads a header holding a signature.
+fn header<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Result<&'a str, SignatureError> {
+    headers
+        .get(name)
+        .ok_or_else(|| SignatureError::Missing(name.clone()))?
+        .to_str()
+        .map(str::trim)
+        .map_err(|_| SignatureError::Malformed(name.clone()))
+}
+
+/// Checks that a webhook signed at `timestamp` is received within
+/// `tolerance`.
+fn check_timestamp(timestamp: u64, now: u64, tolerance: Duration) -> Result<(), SignatureError> {
+    if timestamp.abs_diff(now) > tolerance.as_secs() {
+        return Err(SignatureError::Expired);
+    }
+
+    Ok(())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    pub(in crate::webhook) fn sign(secret: &[u8], content: &[u8]) -> String {
+        let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, secret), content);
+
+        tag.as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn headers(name: HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn verifies_stripe_signatures() {
+        let stripe = Stripe::new("whsec_test");
+        let body = br#"{"id": "evt_1"}"#;
+        let signature = sign(b"whsec_test", &[b"1700000000.", &body[..]].concat());
+
+        let signed = headers(
+            STRIPE_SIGNATURE,
+            &format!("t=1700000000,v1=00ff,v1={signature},v0=abc"),
+        );
+        stripe.verify(&signed, body, 1_700_000_100).unwrap();
+
+        assert!(matches!(
+            stripe.verify(&signed, body, 1_700_000_301),
+            Err(SignatureError::Expired)
+        ));
+        assert!(matches!(
+            stripe.verify(&signed, b"{}", 1_700_000_100),
+            Err(SignatureError::Mismatch)
+        ));
+        assert!(matches!(
+            stripe.verify(&HeaderMap::new(), body, 1_700_000_100),
+            Err(SignatureError::Missing(_))
+        ));
+    }
+
+    #[test]
+    fn verifies_header_signatures() {
+        let body = b"payload";
+        let signature = sign(b"secret", body);
+
+        let github = GitHub::new("secret");
+        github
+            .verify(
+                &headers(GITHUB_SIGNATURE, &format!("sha256={signature}")),
+                body,
+                0,
+            )
+            .unwrap();
+        assert!(matches!(
+            github.verify(&headers(GITHUB_SIGNATURE, &signature), body, 0),
+            Err(SignatureError::Malformed(_))
+        ));
+
+        let header = HeaderName::from_static("x-signature");
+        let base64 = STANDARD.encode(decode_hex(&signature).unwrap());
+        let hmac = Hmac::sha256(header.clone(), b"secret").encoding(Encoding::Base64);
+        hmac.verify(&headers(header.clone(), &base64), body, 0)
+            .unwrap();
+        assert!(matches!(
+            hmac.verify(&headers(header, &base64), b"tampered", 0),
+            Err(SignatureError::Mismatch)
+        ));
+    }
+
+    #[test]
+    fn verifies_timestamped_ed25519_signatures() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let signature_header = HeaderName::from_static("x-signature-ed25519");
+        let timestamp_header = HeaderName::from_static("x-signature-timestamp");
+
+        let verifier =
+            Ed25519::new(signature_header.clone(), key_pair.public_key().as_ref()).timestamp(
+                Timestamp::new(timestamp_header.clone(), Duration::from_secs(60)),
+            );
+
+        let body = br#"{"type": 1}"#;
+        let signature = key_pair.sign(&[b"1700000000", &body[..]].concat());
+
+        let mut signed = headers(
+            signature_header,
+            &STANDARD.encode(signature.as_ref()).replace('=', ""),
+        );
+        assert!(matches!(
+            verifier.verify(&signed, body, 1_700_000_000),
+            Err(SignatureError::Malformed(_))
+        ));
+
+        let hex: String = signature
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        signed.insert(
+            HeaderName::from_static("x-signature-ed25519"),
+            hex.parse().unwrap(),
+        );
+        assert!(matches!(
+            verifier.verify(&signed, body, 1_700_000_000),
+            Err(SignatureError::Missing(_))
+        ));
+
+        signed.insert(timestamp_header, "1700000000".parse().unwrap());
+        verifier.verify(&signed, body, 1_700_000_030).unwrap();
+        assert!(matches!(
+            verifier.verify(&signed, body, 1_700_000_061),
+            Err(SignatureError::Expired)
+        ));
+        assert!(matches!(
+            verifier.verify(&signed, b"{}", 1_700_000_030),
+            Err(SignatureError::Mismatch)
+        ));
+    }
+}